@@ -1,8 +1,30 @@
-use std::io;
+use std::io::{self, Write};
 use std::path::Path;
 use std::process;
 
-use leveldb_parser_lib::{ldb_parser, log_parser, manifest_parser};
+use leveldb_parser_lib::codec::CodecRegistry;
+use leveldb_parser_lib::record_filter::{self, RecordFilter, StateFilter};
+use leveldb_parser_lib::{
+    database, inventory, ioc_sweep, key_timeline, ldb_parser, log_parser, manifest_parser, residue,
+    session_storage, snapshot_diff, verify,
+};
+
+// options followed by a value
+const VALUE_OPTIONS: [&str; 13] = [
+    "--compression",
+    "--sweep",
+    "--diff",
+    "--key",
+    "--prefix",
+    "--start",
+    "--end",
+    "--key-regex",
+    "--value-regex",
+    "--seq-min",
+    "--seq-max",
+    "--state",
+    "--kind",
+];
 
 fn main() {
     if let Err(e) = run() {
@@ -28,14 +50,67 @@ fn run() -> Result<(), Box<dyn std::error::Error>> {
     let args: Vec<String> = std::env::args().collect();
 
     let use_print_all = args.contains(&"-a".to_string());
-    let file_path = args.iter().skip(1).find(|arg| !arg.starts_with('-'));
+    let use_verify = args.contains(&"--verify".to_string());
+    let use_residue = args.contains(&"--residue".to_string());
+    let use_residue_files = args.contains(&"--files".to_string());
+    let use_inventory = args.contains(&"--inventory".to_string());
+    let use_session_storage = args.contains(&"--session-storage".to_string());
+
+    let compression_spec = option_value(&args, "--compression");
+    let filter = parse_filter(&args)?;
+    if use_print_all && !filter.is_empty() {
+        return Err("Filter options only apply to the CSV output, not to -a".into());
+    }
+
+    let file_path = args
+        .iter()
+        .enumerate()
+        .skip(1)
+        .find(|(i, arg)| !arg.starts_with('-') && !VALUE_OPTIONS.contains(&args[i - 1].as_str()))
+        .map(|(_, arg)| arg);
 
     let file_path = match file_path {
         Some(path) => path,
         None => {
-            println!("Usage: {} [-a] <file>", args[0]);
-            println!("  -a     print all details (default is CSV format)");
-            println!("  file   .log, .ldb or MANIFEST file to parse");
+            println!(
+                "Usage: {} [-a] [--compression <spec>] [filter options] <file>",
+                args[0]
+            );
+            println!("  -a                    print all details (default is CSV format)");
+            println!("  --compression <spec>  block codecs of table files: leveldb, bedrock or");
+            println!(
+                "                        <id>=<codec>,... (none, snappy, zstd, zlib, deflate, lz4)"
+            );
+            println!("  file                  .log, .ldb, .sst or MANIFEST file to parse");
+            println!("  --sweep <indicators>  sweep all databases below the directory <file>");
+            println!("                        for the indicators (text, icase:, regex:, md5:,");
+            println!(
+                "                        sha1:, sha256: per line), output is a CSV hit report"
+            );
+            println!("  --diff <old dir>      compare the database directory <file> with an");
+            println!("                        older acquisition of it");
+            println!("  --key <key>           every version of the user key (text or hex:<bytes>)");
+            println!("                        in the database directory <file>");
+            println!("  --verify              check the consistency of the database directory");
+            println!("                        <file> (CURRENT, manifest, tables, CRCs)");
+            println!("  --residue [--files]   records only obsolete or unreferenced files of the");
+            println!("                        database directory <file> hold, or with --files the");
+            println!("                        classification of its files");
+            println!("  --session-storage     Session Storage entries of the database directory");
+            println!("                        <file> with their namespace and origin");
+            println!("  --inventory           files of the database directory <file> by LevelDB");
+            println!("                        file name pattern, anomalies on stderr");
+            println!();
+            println!("Filter options (CSV output, keys as text or hex:<bytes>):");
+            println!("  --prefix <key>        user key prefix");
+            println!("  --start <key>         first user key (inclusive)");
+            println!("  --end <key>           last user key (exclusive)");
+            println!("  --key-regex <regex>   regex on the decoded key");
+            println!("  --value-regex <regex> regex on the decoded value");
+            println!("  --seq-min <n>         lowest sequence number");
+            println!("  --seq-max <n>         highest sequence number");
+            println!("  --state <state>       live, deleted or all");
+            println!("  --kind <kinds>        decoded kinds, e.g. L,S or I (all IndexedDB)");
             return Ok(());
         }
     };
@@ -50,6 +125,129 @@ fn run() -> Result<(), Box<dyn std::error::Error>> {
         }
     };
 
+    if let Some(indicators_path) = option_value(&args, "--sweep") {
+        let indicators = ioc_sweep::load_indicators(indicators_path)?;
+        let report = ioc_sweep::sweep(&abs_path, &indicators);
+        for error in &report.errors {
+            eprintln!("Error: {}", error);
+        }
+        eprintln!(
+            "Swept {} database(s), {} hit(s)",
+            report.databases.len(),
+            report.hits.len()
+        );
+        write!(io::stdout(), "{}", ioc_sweep::export::csv_string(&report))?;
+        return Ok(());
+    }
+
+    if let Some(old_dir) = option_value(&args, "--diff") {
+        let old_db = database::parse_dir(old_dir)?;
+        let new_db = database::parse_dir(abs_path.to_str().unwrap())?;
+        for error in old_db.parse_errors.iter().chain(&new_db.parse_errors) {
+            eprintln!("Error: {}", error);
+        }
+        let (entries, summary) = snapshot_diff::diff_databases(&old_db, &new_db);
+        eprintln!(
+            "Added: {}, Removed: {}, Changed: {}, Rewritten: {}, Tombstoned: {}, Unchanged: {}",
+            summary.added,
+            summary.removed,
+            summary.changed,
+            summary.rewritten,
+            summary.tombstoned,
+            summary.unchanged
+        );
+        write!(
+            io::stdout(),
+            "{}",
+            snapshot_diff::export::csv_string(&entries)
+        )?;
+        return Ok(());
+    }
+
+    if use_verify {
+        let report = verify::verify_dir(abs_path.to_str().unwrap())?;
+        if use_print_all {
+            verify::display::print_report(&report)?;
+        } else {
+            write!(io::stdout(), "{}", verify::export::csv_string(&report))?;
+        }
+        return Ok(());
+    }
+
+    if use_inventory {
+        let inventory = inventory::inventory_dir(abs_path.to_str().unwrap())?;
+        if use_print_all {
+            inventory::display::print_inventory(&inventory)?;
+        } else {
+            for anomaly in &inventory.anomalies {
+                eprintln!("Anomaly: {}", anomaly);
+            }
+            write!(
+                io::stdout(),
+                "{}",
+                inventory::export::csv_string(&inventory)
+            )?;
+        }
+        return Ok(());
+    }
+
+    if use_session_storage {
+        let db = database::parse_dir(abs_path.to_str().unwrap())?;
+        for error in &db.parse_errors {
+            eprintln!("Error: {}", error);
+        }
+        let session = session_storage::build(&database::collect_records(&db));
+        write!(
+            io::stdout(),
+            "{}",
+            session_storage::export::csv_string(&session)
+        )?;
+        return Ok(());
+    }
+
+    if use_residue {
+        let report = residue::analyze_dir(abs_path.to_str().unwrap())?;
+        for error in &report.errors {
+            eprintln!("Error: {}", error);
+        }
+        if use_print_all {
+            residue::display::print_report(&report)?;
+        } else if use_residue_files {
+            write!(
+                io::stdout(),
+                "{}",
+                residue::export::files_csv_string(&report)
+            )?;
+        } else {
+            for file in &report.files {
+                if file.status != residue::FileStatus::Live {
+                    eprintln!("{}: {}", file.status.name(), file.file_name);
+                }
+            }
+            write!(
+                io::stdout(),
+                "{}",
+                residue::export::records_csv_string(&report)
+            )?;
+        }
+        return Ok(());
+    }
+
+    if let Some(key) = option_value(&args, "--key") {
+        let user_key = record_filter::parse_key_arg(key)?;
+        let db = database::parse_dir(abs_path.to_str().unwrap())?;
+        for error in &db.parse_errors {
+            eprintln!("Error: {}", error);
+        }
+        let entries = key_timeline::key_timeline(&db, &user_key);
+        write!(
+            io::stdout(),
+            "{}",
+            key_timeline::export::csv_string(&entries)
+        )?;
+        return Ok(());
+    }
+
     let file_name = if !path.exists() {
         println!("Error: File does not exist: {}", path.display());
         return Ok(());
@@ -60,19 +258,31 @@ fn run() -> Result<(), Box<dyn std::error::Error>> {
         return Ok(());
     };
 
-    if file_name.ends_with(".ldb") {
-        let ldb_file = ldb_parser::parse_file(abs_path.to_str().unwrap())?;
+    if file_name.ends_with(".ldb") || file_name.ends_with(".sst") {
+        let codecs = match compression_spec {
+            Some(spec) => CodecRegistry::from_spec(spec)?,
+            None => CodecRegistry::for_path(abs_path.to_str().unwrap()),
+        };
+        let ldb_file = ldb_parser::parse_file_with_codecs(abs_path.to_str().unwrap(), &codecs)?;
         if use_print_all {
             ldb_parser::display::print_all(&ldb_file)?;
         } else {
-            ldb_parser::display::print_csv(&ldb_file)?;
+            ldb_parser::display::print_csv_filtered(
+                &ldb_file,
+                abs_path.to_str().unwrap(),
+                &filter,
+            )?;
         }
     } else if file_name.ends_with(".log") {
         let log_file = log_parser::parse_file(abs_path.to_str().unwrap())?;
         if use_print_all {
             log_parser::display::print_all(&log_file)?;
         } else {
-            log_parser::display::print_csv(&log_file)?;
+            log_parser::display::print_csv_filtered(
+                &log_file,
+                abs_path.to_str().unwrap(),
+                &filter,
+            )?;
         }
     } else if file_name.starts_with("MANIFEST-") {
         let manifest_file = manifest_parser::parse_file(abs_path.to_str().unwrap())?;
@@ -87,3 +297,48 @@ fn run() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+fn option_value<'a>(args: &'a [String], name: &str) -> Option<&'a String> {
+    let pos = args.iter().position(|arg| arg == name)?;
+    args.get(pos + 1)
+}
+
+fn parse_filter(args: &[String]) -> Result<RecordFilter, String> {
+    let mut filter = RecordFilter::default();
+
+    if let Some(prefix) = option_value(args, "--prefix") {
+        filter.prefix = Some(record_filter::parse_key_arg(prefix)?);
+    }
+    if let Some(start) = option_value(args, "--start") {
+        filter.start = Some(record_filter::parse_key_arg(start)?);
+    }
+    if let Some(end) = option_value(args, "--end") {
+        filter.end = Some(record_filter::parse_key_arg(end)?);
+    }
+    if let Some(pattern) = option_value(args, "--key-regex") {
+        filter.set_key_regex(pattern)?;
+    }
+    if let Some(pattern) = option_value(args, "--value-regex") {
+        filter.set_value_regex(pattern)?;
+    }
+    if let Some(seq) = option_value(args, "--seq-min") {
+        filter.seq_min = Some(parse_seq(seq)?);
+    }
+    if let Some(seq) = option_value(args, "--seq-max") {
+        filter.seq_max = Some(parse_seq(seq)?);
+    }
+    if let Some(state) = option_value(args, "--state") {
+        filter.state =
+            StateFilter::from_name(state).ok_or_else(|| format!("Invalid state '{}'", state))?;
+    }
+    if let Some(kinds) = option_value(args, "--kind") {
+        filter.set_kinds(kinds);
+    }
+
+    Ok(filter)
+}
+
+fn parse_seq(seq: &str) -> Result<u64, String> {
+    seq.parse()
+        .map_err(|_| format!("Invalid sequence number '{}'", seq))
+}
@@ -52,14 +52,8 @@ function applyViewFilter(kindValue, previousKind) {
     // other views
     const model = { ...(recordsGrid.getFilterModel() || {}) };
     if (kindValue === 'I') {
-        model.Kind = {
-            filterType: 'text',
-            operator: 'OR',
-            conditions: [
-                { type: 'equals', filter: 'I' },
-                { type: 'equals', filter: 'IE' },
-            ],
-        };
+        // I, IE, II
+        model.Kind = { filterType: 'text', type: 'startsWith', filter: 'I' };
     } else {
         model.Kind = { filterType: 'text', type: 'equals', filter: kindValue };
     }
@@ -355,7 +349,7 @@ listen('records_csv', e => {
 
     // create view filter dropdown dynamically
     const kindSet = new Set();
-    // data.Kind column can contain: S, L, I, IE, G
+    // data.Kind column can contain: S, L, I, IE, II, X, SY, GC, SW, SC, SP, PN, P, G
     recordsGrid.forEachNode(node => {
         if (node.data?.Kind) {
             kindSet.add(node.data.Kind);
@@ -367,6 +361,18 @@ listen('records_csv', e => {
         L: 'Local Storage',
         I: 'IndexedDB',
         IE: 'IndexedDB (Entries)',
+        II: 'IndexedDB (Index)',
+        X: 'Extension Settings',
+        SY: 'Sync Data',
+        GC: 'GCM Store',
+        SW: 'Service Worker',
+        SC: 'Site Characteristics',
+        SP: 'Shared Proto DB',
+        PN: 'Platform Notifications',
+        MC: 'Minecraft Bedrock',
+        BC: 'Bitcoin Chainstate',
+        BB: 'Bitcoin Block Index',
+        P: 'Protobuf',
         G: 'Generic (UTF-8)'
     };
 
@@ -0,0 +1,223 @@
+use std::path::Path;
+
+use crate::decoder;
+use crate::nbt;
+
+// Minecraft Bedrock world database (<world>/db next to <world>/level.dat)
+//   chunk keys: x (i32) | z (i32) | [dimension (i32)] | tag (u8) | [subchunk (i8)]
+//   other keys: plain names (~local_player, BiomeData, ...) or prefixed ids
+//   (actorprefix<id>, digp<chunk key>), values are mostly little-endian NBT
+// -----------------------------------------------------------------------------
+const TAG_SUBCHUNK_PREFIX: u8 = 47;
+const TAG_VERSION: u8 = 44;
+const TAG_LEGACY_VERSION: u8 = 118;
+
+// marker keys of the world database
+const MARKER_KEYS: [&[u8]; 10] = [
+    b"~local_player",
+    b"AutonomousEntities",
+    b"BiomeData",
+    b"mobevents",
+    b"scoreboard",
+    b"portals",
+    b"Overworld",
+    b"Nether",
+    b"TheEnd",
+    b"LevelChunkMetaDataDictionary",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChunkKey {
+    pub x: i32,
+    pub z: i32,
+    pub dimension: i32, // 0 overworld, 1 nether, 2 end
+    pub tag: u8,
+    pub subchunk: Option<i8>,
+}
+
+// -----------------------------------------------------------------------------
+// .ldb/.log file inside the db directory of a world
+pub fn is_world_db_file(file_path: &Path) -> bool {
+    file_path.parent().is_some_and(is_world_db_dir)
+}
+
+pub fn is_world_db_dir(dir_path: &Path) -> bool {
+    let is_db_dir = dir_path
+        .file_name()
+        .is_some_and(|name| name.eq_ignore_ascii_case("db"));
+    is_db_dir
+        && dir_path
+            .parent()
+            .is_some_and(|world| world.join("level.dat").is_file())
+}
+
+pub fn is_marker_key(key: &[u8]) -> bool {
+    MARKER_KEYS.contains(&key) || key.starts_with(b"player_") || key.starts_with(b"actorprefix")
+}
+
+pub fn decode_chunk_key(key: &[u8]) -> Option<ChunkKey> {
+    let (has_dimension, has_subchunk) = match key.len() {
+        9 => (false, false),
+        10 => (false, true),
+        13 => (true, false),
+        14 => (true, true),
+        _ => return None,
+    };
+
+    let x = i32::from_le_bytes(key[0..4].try_into().ok()?);
+    let z = i32::from_le_bytes(key[4..8].try_into().ok()?);
+    let (dimension, tag_pos) = if has_dimension {
+        (i32::from_le_bytes(key[8..12].try_into().ok()?), 12)
+    } else {
+        (0, 8)
+    };
+    let tag = key[tag_pos];
+
+    if !(0..=2).contains(&dimension) || chunk_tag_name(tag).is_none() {
+        return None;
+    }
+    // only subchunk prefix records carry the subchunk index
+    if has_subchunk != (tag == TAG_SUBCHUNK_PREFIX) {
+        return None;
+    }
+
+    Some(ChunkKey {
+        x,
+        z,
+        dimension,
+        tag,
+        subchunk: has_subchunk.then(|| key[tag_pos + 1] as i8),
+    })
+}
+
+pub fn chunk_tag_name(tag: u8) -> Option<&'static str> {
+    let name = match tag {
+        43 => "Data3D",
+        44 => "Version",
+        45 => "Data2D",
+        46 => "Data2DLegacy",
+        47 => "SubChunkPrefix",
+        48 => "LegacyTerrain",
+        49 => "BlockEntity",
+        50 => "Entity",
+        51 => "PendingTicks",
+        52 => "LegacyBlockExtraData",
+        53 => "BiomeState",
+        54 => "FinalizedState",
+        55 => "ConversionData",
+        56 => "BorderBlocks",
+        57 => "HardcodedSpawners",
+        58 => "RandomTicks",
+        59 => "CheckSums",
+        60 => "GenerationSeed",
+        61 => "GeneratedPreCavesAndCliffsBlending",
+        62 => "BlendingBiomeHeight",
+        63 => "MetaDataHash",
+        64 => "BlendingData",
+        65 => "ActorDigestVersion",
+        118 => "LegacyVersion",
+        _ => return None,
+    };
+    Some(name)
+}
+
+pub fn dimension_name(dimension: i32) -> &'static str {
+    match dimension {
+        0 => "overworld",
+        1 => "nether",
+        2 => "end",
+        _ => "unknown",
+    }
+}
+
+pub fn chunk_key_to_string(chunk_key: &ChunkKey) -> String {
+    let mut s = format!(
+        "chunk({}, {}) {} {}",
+        chunk_key.x,
+        chunk_key.z,
+        dimension_name(chunk_key.dimension),
+        chunk_tag_name(chunk_key.tag).unwrap_or("Unknown"),
+    );
+    if let Some(subchunk) = chunk_key.subchunk {
+        s.push_str(&format!(" [{}]", subchunk));
+    }
+    s
+}
+
+// -----------------------------------------------------------------------------
+pub fn decode_key(key: &[u8]) -> String {
+    if let Some(chunk_key) = decode_chunk_key(key) {
+        return chunk_key_to_string(&chunk_key);
+    }
+    if let Some(rest) = key.strip_prefix(b"digp")
+        && let Some(chunk_key) = decode_chunk_key_prefix(rest)
+    {
+        return format!("digp {}", chunk_key);
+    }
+    if let Some(id) = key.strip_prefix(b"actorprefix")
+        && id.len() == 8
+    {
+        return format!("actorprefix {}", actor_id_to_string(id));
+    }
+    decoder::bytes_to_ascii_with_hex(key)
+}
+
+pub fn decode_value(key: &[u8], value: &[u8]) -> String {
+    if let Some(chunk_key) = decode_chunk_key(key) {
+        match chunk_key.tag {
+            TAG_VERSION | TAG_LEGACY_VERSION if value.len() == 1 => {
+                return format!("version {}", value[0]);
+            }
+            TAG_SUBCHUNK_PREFIX if !value.is_empty() => {
+                return format!(
+                    "subchunk version {}: {}",
+                    value[0],
+                    decoder::bytes_to_hex(&value[1..])
+                );
+            }
+            _ => {}
+        }
+    }
+
+    // digp: actor ids of the chunk, "actorprefix" + id is the key of the actor
+    if key.starts_with(b"digp") && value.len().is_multiple_of(8) {
+        let ids: Vec<String> = value.chunks_exact(8).map(actor_id_to_string).collect();
+        return format!("[{}]", ids.join(", "));
+    }
+
+    match nbt::decode_nbt_value(value) {
+        Some(s) => s,
+        None => match std::str::from_utf8(value) {
+            Ok(s) if !s.is_empty() && !s.chars().any(char::is_control) => s.to_string(),
+            _ => decoder::bytes_to_hex(value),
+        },
+    }
+}
+
+// 8 byte actor storage id as hex, the same in digp values and actorprefix keys
+fn actor_id_to_string(id: &[u8]) -> String {
+    id.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+// "x, z[, dimension]" of the 8 or 12 byte chunk position
+fn decode_chunk_key_prefix(bytes: &[u8]) -> Option<String> {
+    let read_i32 = |pos: usize| -> Option<i32> {
+        Some(i32::from_le_bytes(
+            bytes.get(pos..pos + 4)?.try_into().ok()?,
+        ))
+    };
+    match bytes.len() {
+        8 => Some(format!(
+            "chunk({}, {}) overworld",
+            read_i32(0)?,
+            read_i32(4)?
+        )),
+        12 => Some(format!(
+            "chunk({}, {}) {}",
+            read_i32(0)?,
+            read_i32(4)?,
+            dimension_name(read_i32(8)?)
+        )),
+        _ => None,
+    }
+}
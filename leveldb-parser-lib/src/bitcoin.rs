@@ -0,0 +1,1001 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+
+use sha2::{Digest, Sha256};
+
+use crate::database::DbRecord;
+use crate::decoder;
+use crate::decoder_registry::{DecodeContext, ValueDecoder};
+use crate::log_parser;
+use crate::table_reader::TableReader;
+
+// Bitcoin Core LevelDB databases (see Bitcoin Core txdb.cpp / chain.h)
+//   chainstate:   C<txid><VARINT vout> => Coin, B => best block hash,
+//                 \x0e\x00obfuscate_key => XOR key applied to all other values
+//   blocks/index: b<block hash> => CDiskBlockIndex, f<file no> => CBlockFileInfo,
+//                 l => last block file, R => reindexing, F<name> => flag
+// hashes are stored little-endian and displayed reversed
+// -----------------------------------------------------------------------------
+pub const OBFUSCATE_KEY_KEY: &[u8] = b"\x0e\x00obfuscate_key";
+
+const COIN_PREFIX: u8 = b'C';
+const BEST_BLOCK_KEY: &[u8] = b"B";
+const HEAD_BLOCKS_KEY: &[u8] = b"H";
+const BLOCK_INDEX_PREFIX: u8 = b'b';
+const BLOCK_FILE_INFO_PREFIX: u8 = b'f';
+const LAST_BLOCK_FILE_KEY: &[u8] = b"l";
+const REINDEX_KEY: &[u8] = b"R";
+const FLAG_PREFIX: u8 = b'F';
+
+// block status flags (chain.h)
+const BLOCK_VALID_MASK: u64 = 0x07;
+const BLOCK_HAVE_DATA: u64 = 0x08;
+const BLOCK_HAVE_UNDO: u64 = 0x10;
+const BLOCK_FAILED_VALID: u64 = 0x20;
+const BLOCK_FAILED_CHILD: u64 = 0x40;
+const BLOCK_OPT_WITNESS: u64 = 0x80;
+
+const BASE58_ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+const BECH32_CHARSET: &[u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const BECH32_CONST: u32 = 1;
+const BECH32M_CONST: u32 = 0x2bc8_30a3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Network {
+    Main,
+    Test, // testnet3 / testnet4 / signet
+    Regtest,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScriptType {
+    P2pkh,
+    P2sh,
+    P2pk,
+    P2wpkh,
+    P2wsh,
+    P2tr,
+    WitnessUnknown,
+    OpReturn,
+    NonStandard,
+}
+
+pub struct Script {
+    pub script_type: ScriptType,
+    pub data: Vec<u8>, // hash / public key / witness program, raw script otherwise
+    pub address: Option<String>,
+}
+
+pub struct Coin {
+    pub height: u64,
+    pub coinbase: bool,
+    pub amount: u64, // satoshis
+    pub script: Script,
+}
+
+pub struct CoinRecord {
+    pub txid: String,
+    pub vout: u64,
+    pub coin: Option<Coin>, // None if deleted (spent) or not decodable
+    pub seq: u64,
+    pub state: u8,
+    pub file_path: String,
+}
+
+pub struct Chainstate {
+    pub obfuscate_key: Option<Vec<u8>>,
+    pub best_block: Option<String>,
+    pub coins: Vec<CoinRecord>,
+}
+
+pub struct BlockIndexEntry {
+    pub hash: String,
+    pub height: u64,
+    pub status: u64,
+    pub tx_count: u64,
+    pub file: Option<u64>,
+    pub data_pos: Option<u64>,
+    pub undo_pos: Option<u64>,
+    pub version: i32,
+    pub prev_hash: String,
+    pub merkle_root: String,
+    pub time: u32,
+    pub bits: u32,
+    pub nonce: u32,
+    pub seq: u64,
+    pub state: u8,
+    pub file_path: String,
+}
+
+pub struct BlockFileInfo {
+    pub file_no: i32,
+    pub blocks: u64,
+    pub size: u64,
+    pub undo_size: u64,
+    pub height_first: u64,
+    pub height_last: u64,
+    pub time_first: u64,
+    pub time_last: u64,
+}
+
+pub struct BlockIndex {
+    pub blocks: Vec<BlockIndexEntry>,
+    pub block_files: Vec<BlockFileInfo>,
+    pub last_block_file: Option<i32>,
+}
+
+// -----------------------------------------------------------------------------
+pub fn network_from_path(path: &str) -> Network {
+    let lower = path.to_ascii_lowercase();
+    if lower.contains("regtest") {
+        Network::Regtest
+    } else if lower.contains("testnet") || lower.contains("signet") {
+        Network::Test
+    } else {
+        Network::Main
+    }
+}
+
+// XOR key of the chainstate, the value is a length prefixed byte vector
+pub fn parse_obfuscate_key(value: &[u8]) -> Option<Vec<u8>> {
+    let mut i = 0;
+    let len = read_compact_size(value, &mut i)? as usize;
+    let key = value.get(i..i.checked_add(len)?)?;
+    (i + len == value.len()).then(|| key.to_vec())
+}
+
+pub fn find_obfuscate_key(records: &[DbRecord]) -> Option<Vec<u8>> {
+    records
+        .iter()
+        .filter(|record| record.key == OBFUSCATE_KEY_KEY)
+        .filter_map(|record| record.value.as_deref().and_then(parse_obfuscate_key))
+        .next_back()
+}
+
+pub fn deobfuscate(value: &[u8], obfuscate_key: &[u8]) -> Vec<u8> {
+    if obfuscate_key.is_empty() {
+        return value.to_vec();
+    }
+    value
+        .iter()
+        .zip(obfuscate_key.iter().cycle())
+        .map(|(b, k)| b ^ k)
+        .collect()
+}
+
+// "C" + txid + VARINT(vout)
+pub fn decode_coin_key(key: &[u8]) -> Option<(String, u64)> {
+    let rest = key.strip_prefix(&[COIN_PREFIX])?;
+    let txid = rest.get(..32)?;
+    let mut i = 32;
+    let vout = read_varint(rest, &mut i)?;
+    (i == rest.len()).then(|| (hash_to_string(txid), vout))
+}
+
+// deobfuscated value of a coin
+pub fn decode_coin(value: &[u8], network: Network) -> Option<Coin> {
+    let mut i = 0;
+    let code = read_varint(value, &mut i)?;
+    let amount = decompress_amount(read_varint(value, &mut i)?);
+    let script = decompress_script(value, &mut i, network)?;
+    if i != value.len() {
+        return None;
+    }
+    Some(Coin {
+        height: code >> 1,
+        coinbase: code & 1 == 1,
+        amount,
+        script,
+    })
+}
+
+// "b" + block hash
+pub fn decode_block_index_entry(key: &[u8], value: &[u8]) -> Option<BlockIndexEntry> {
+    let hash = key.strip_prefix(&[BLOCK_INDEX_PREFIX])?;
+    if hash.len() != 32 {
+        return None;
+    }
+
+    let mut i = 0;
+    let _client_version = read_varint(value, &mut i)?;
+    let height = read_varint(value, &mut i)?;
+    let status = read_varint(value, &mut i)?;
+    let tx_count = read_varint(value, &mut i)?;
+    let file = if status & (BLOCK_HAVE_DATA | BLOCK_HAVE_UNDO) != 0 {
+        Some(read_varint(value, &mut i)?)
+    } else {
+        None
+    };
+    let data_pos = if status & BLOCK_HAVE_DATA != 0 {
+        Some(read_varint(value, &mut i)?)
+    } else {
+        None
+    };
+    let undo_pos = if status & BLOCK_HAVE_UNDO != 0 {
+        Some(read_varint(value, &mut i)?)
+    } else {
+        None
+    };
+
+    // block header
+    let header = value.get(i..i + 80)?;
+    if i + 80 != value.len() {
+        return None;
+    }
+
+    Some(BlockIndexEntry {
+        hash: hash_to_string(hash),
+        height,
+        status,
+        tx_count,
+        file,
+        data_pos,
+        undo_pos,
+        version: i32::from_le_bytes(header[0..4].try_into().ok()?),
+        prev_hash: hash_to_string(&header[4..36]),
+        merkle_root: hash_to_string(&header[36..68]),
+        time: u32::from_le_bytes(header[68..72].try_into().ok()?),
+        bits: u32::from_le_bytes(header[72..76].try_into().ok()?),
+        nonce: u32::from_le_bytes(header[76..80].try_into().ok()?),
+        seq: 0,
+        state: 0,
+        file_path: String::new(),
+    })
+}
+
+// "f" + file number
+pub fn decode_block_file_info(key: &[u8], value: &[u8]) -> Option<BlockFileInfo> {
+    let file_no = key.strip_prefix(&[BLOCK_FILE_INFO_PREFIX])?;
+    let file_no = i32::from_le_bytes(file_no.try_into().ok()?);
+
+    let mut i = 0;
+    let mut fields = [0u64; 7];
+    for field in &mut fields {
+        *field = read_varint(value, &mut i)?;
+    }
+    if i != value.len() {
+        return None;
+    }
+
+    Some(BlockFileInfo {
+        file_no,
+        blocks: fields[0],
+        size: fields[1],
+        undo_size: fields[2],
+        height_first: fields[3],
+        height_last: fields[4],
+        time_first: fields[5],
+        time_last: fields[6],
+    })
+}
+
+pub fn block_status_to_string(status: u64) -> String {
+    let mut flags = vec![
+        match status & BLOCK_VALID_MASK {
+            0 => "unknown",
+            1 => "reserved",
+            2 => "valid-tree",
+            3 => "valid-transactions",
+            4 => "valid-chain",
+            5 => "valid-scripts",
+            _ => "valid-?",
+        }
+        .to_string(),
+    ];
+    for (flag, name) in [
+        (BLOCK_HAVE_DATA, "have-data"),
+        (BLOCK_HAVE_UNDO, "have-undo"),
+        (BLOCK_FAILED_VALID, "failed-valid"),
+        (BLOCK_FAILED_CHILD, "failed-child"),
+        (BLOCK_OPT_WITNESS, "witness"),
+    ] {
+        if status & flag != 0 {
+            flags.push(name.to_string());
+        }
+    }
+    flags.join("|")
+}
+
+pub fn script_type_name(script_type: ScriptType) -> &'static str {
+    match script_type {
+        ScriptType::P2pkh => "p2pkh",
+        ScriptType::P2sh => "p2sh",
+        ScriptType::P2pk => "p2pk",
+        ScriptType::P2wpkh => "p2wpkh",
+        ScriptType::P2wsh => "p2wsh",
+        ScriptType::P2tr => "p2tr",
+        ScriptType::WitnessUnknown => "witness_unknown",
+        ScriptType::OpReturn => "op_return",
+        ScriptType::NonStandard => "nonstandard",
+    }
+}
+
+pub fn coin_to_string(coin: &Coin) -> String {
+    format!(
+        "height: {}{}, amount: {} BTC, {}: {}",
+        coin.height,
+        if coin.coinbase { " (coinbase)" } else { "" },
+        format_btc(coin.amount),
+        script_type_name(coin.script.script_type),
+        match &coin.script.address {
+            Some(address) => address.clone(),
+            None => decoder::bytes_to_hex_raw(&coin.script.data)
+                .trim_end()
+                .to_string(),
+        }
+    )
+}
+
+pub fn block_index_entry_to_string(entry: &BlockIndexEntry) -> String {
+    let mut s = format!(
+        "height: {}, status: {}, txs: {}",
+        entry.height,
+        block_status_to_string(entry.status),
+        entry.tx_count
+    );
+    if let Some(file) = entry.file {
+        s.push_str(&format!(", file: blk{:05}.dat", file));
+    }
+    if let Some(data_pos) = entry.data_pos {
+        s.push_str(&format!(", data pos: {}", data_pos));
+    }
+    if let Some(undo_pos) = entry.undo_pos {
+        s.push_str(&format!(", undo pos: {}", undo_pos));
+    }
+    s.push_str(&format!(
+        ", version: 0x{:08X}, prev: {}, merkle root: {}, time: {}, bits: 0x{:08X}, nonce: {}",
+        entry.version,
+        entry.prev_hash,
+        entry.merkle_root,
+        decoder::format_unix_timestamp(entry.time as i64),
+        entry.bits,
+        entry.nonce
+    ));
+    s
+}
+
+pub fn format_btc(satoshis: u64) -> String {
+    format!("{}.{:08}", satoshis / 100_000_000, satoshis % 100_000_000)
+}
+
+// -----------------------------------------------------------------------------
+// all coin records in sequence order, values deobfuscated with the key of the
+// database
+pub fn build_chainstate(records: &[DbRecord], network: Network) -> Chainstate {
+    let obfuscate_key = find_obfuscate_key(records);
+    let xor_key = obfuscate_key.as_deref().unwrap_or(&[]);
+
+    let mut best_block = None;
+    let mut coins = Vec::new();
+
+    let mut sorted: Vec<&DbRecord> = records.iter().collect();
+    sorted.sort_by_key(|record| record.seq);
+
+    for record in sorted {
+        let value = record.value.as_deref().map(|v| deobfuscate(v, xor_key));
+
+        if record.key == BEST_BLOCK_KEY {
+            if let Some(hash) = value.filter(|v| v.len() == 32) {
+                best_block = Some(hash_to_string(&hash));
+            }
+        } else if let Some((txid, vout)) = decode_coin_key(&record.key) {
+            coins.push(CoinRecord {
+                txid,
+                vout,
+                coin: value.and_then(|v| decode_coin(&v, network)),
+                seq: record.seq,
+                state: record.state,
+                file_path: record.file_path.clone(),
+            });
+        }
+    }
+
+    Chainstate {
+        obfuscate_key,
+        best_block,
+        coins,
+    }
+}
+
+pub fn build_block_index(records: &[DbRecord]) -> BlockIndex {
+    let mut blocks = Vec::new();
+    let mut block_files: Vec<BlockFileInfo> = Vec::new();
+    let mut last_block_file = None;
+
+    let mut sorted: Vec<&DbRecord> = records.iter().collect();
+    sorted.sort_by_key(|record| record.seq);
+
+    for record in sorted {
+        let Some(value) = record.value.as_deref() else {
+            continue;
+        };
+
+        if let Some(mut entry) = decode_block_index_entry(&record.key, value) {
+            entry.seq = record.seq;
+            entry.state = record.state;
+            entry.file_path = record.file_path.clone();
+            blocks.push(entry);
+        } else if let Some(info) = decode_block_file_info(&record.key, value) {
+            // latest info per file
+            block_files.retain(|f| f.file_no != info.file_no);
+            block_files.push(info);
+        } else if record.key == LAST_BLOCK_FILE_KEY
+            && let Ok(file_no) = <[u8; 4]>::try_from(value)
+        {
+            last_block_file = Some(i32::from_le_bytes(file_no));
+        }
+    }
+
+    blocks.sort_by_key(|entry| entry.height);
+    block_files.sort_by_key(|f| f.file_no);
+
+    BlockIndex {
+        blocks,
+        block_files,
+        last_block_file,
+    }
+}
+
+// -----------------------------------------------------------------------------
+// row decoding without the obfuscation key: keys are decoded, chainstate
+// values stay obfuscated (use ChainstateDecoder to decode them)
+pub fn decode_chainstate_kv(key: &[u8], value: Option<&[u8]>) -> (String, String) {
+    let k = decode_chainstate_key(key);
+    let v = match value {
+        Some(v_bytes) if key == OBFUSCATE_KEY_KEY => match parse_obfuscate_key(v_bytes) {
+            Some(xor_key) => decoder::bytes_to_hex_raw(&xor_key).trim_end().to_string(),
+            None => decoder::bytes_to_hex(v_bytes),
+        },
+        Some(v_bytes) => decoder::bytes_to_hex(v_bytes),
+        None => String::new(),
+    };
+    (k, v)
+}
+
+pub fn decode_chainstate_key(key: &[u8]) -> String {
+    if let Some((txid, vout)) = decode_coin_key(key) {
+        return format!("{}:{}", txid, vout);
+    }
+    if key == OBFUSCATE_KEY_KEY {
+        return "obfuscate_key".to_string();
+    }
+    if key == BEST_BLOCK_KEY {
+        return "best block".to_string();
+    }
+    if key == HEAD_BLOCKS_KEY {
+        return "head blocks".to_string();
+    }
+    decoder::bytes_to_ascii_with_hex(key)
+}
+
+pub fn decode_block_index_kv(key: &[u8], value: Option<&[u8]>) -> (String, String) {
+    let k = match key.split_first() {
+        Some((&BLOCK_INDEX_PREFIX, hash)) if hash.len() == 32 => {
+            format!("block {}", hash_to_string(hash))
+        }
+        Some((&BLOCK_FILE_INFO_PREFIX, file_no)) if file_no.len() == 4 => format!(
+            "file blk{:05}.dat",
+            i32::from_le_bytes(file_no.try_into().unwrap())
+        ),
+        Some((&FLAG_PREFIX, name)) => {
+            let mut i = 0;
+            match read_compact_size(name, &mut i) {
+                Some(len) if i + len as usize == name.len() => {
+                    format!("flag {}", decoder::bytes_to_utf8_lossy(&name[i..]))
+                }
+                _ => decoder::bytes_to_ascii_with_hex(key),
+            }
+        }
+        _ if key == LAST_BLOCK_FILE_KEY => "last block file".to_string(),
+        _ if key == REINDEX_KEY => "reindexing".to_string(),
+        _ => decoder::bytes_to_ascii_with_hex(key),
+    };
+
+    let v = match value {
+        Some(v_bytes) => {
+            if let Some(entry) = decode_block_index_entry(key, v_bytes) {
+                block_index_entry_to_string(&entry)
+            } else if let Some(info) = decode_block_file_info(key, v_bytes) {
+                format!(
+                    "blocks: {}, size: {}, undo size: {}, heights: {}..{}, time: {} .. {}",
+                    info.blocks,
+                    info.size,
+                    info.undo_size,
+                    info.height_first,
+                    info.height_last,
+                    decoder::format_unix_timestamp(info.time_first as i64),
+                    decoder::format_unix_timestamp(info.time_last as i64)
+                )
+            } else if key == LAST_BLOCK_FILE_KEY && v_bytes.len() == 4 {
+                i32::from_le_bytes(v_bytes.try_into().unwrap()).to_string()
+            } else {
+                decoder::bytes_to_ascii_with_hex(v_bytes)
+            }
+        }
+        None => String::new(),
+    };
+    (k, v)
+}
+
+// chainstate rows with the obfuscation key of the database
+pub struct ChainstateDecoder {
+    pub obfuscate_key: Vec<u8>,
+}
+
+impl ChainstateDecoder {
+    pub fn new(obfuscate_key: Vec<u8>) -> Self {
+        ChainstateDecoder { obfuscate_key }
+    }
+}
+
+impl ValueDecoder for ChainstateDecoder {
+    fn name(&self) -> &str {
+        "bitcoin_chainstate_deobfuscated"
+    }
+
+    fn decode(&self, ctx: &DecodeContext) -> (String, String, String) {
+        decode_chainstate_row(ctx, &self.obfuscate_key)
+    }
+}
+
+// chainstate rows with the obfuscation key of the directory of each file,
+// read once per directory; without a key the rows are decoded as in
+// decoder::decode_kv
+#[derive(Default)]
+pub struct ChainstateDirDecoder {
+    obfuscate_keys: Mutex<HashMap<String, Option<Vec<u8>>>>,
+}
+
+impl ValueDecoder for ChainstateDirDecoder {
+    fn name(&self) -> &str {
+        "bitcoin_chainstate"
+    }
+
+    fn decode(&self, ctx: &DecodeContext) -> (String, String, String) {
+        let dir = Path::new(ctx.path).parent().unwrap_or(Path::new(""));
+        let obfuscate_key = {
+            let mut keys = self.obfuscate_keys.lock().unwrap();
+            keys.entry(dir.to_string_lossy().to_string())
+                .or_insert_with(|| read_obfuscate_key(dir))
+                .clone()
+        };
+
+        match obfuscate_key {
+            Some(obfuscate_key) => decode_chainstate_row(ctx, &obfuscate_key),
+            None => {
+                let (k, v) = decode_chainstate_kv(ctx.key, ctx.value);
+                (k, v, "BC".to_string())
+            }
+        }
+    }
+}
+
+// newest obfuscation key row of the tables and logs of a chainstate directory
+pub fn read_obfuscate_key(dir: &Path) -> Option<Vec<u8>> {
+    if dir.as_os_str().is_empty() {
+        return None;
+    }
+
+    // (seq, value) of the rows
+    let mut rows: Vec<(u64, Option<Vec<u8>>)> = Vec::new();
+    for entry in fs::read_dir(dir).ok()?.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let path = entry.path().to_string_lossy().to_string();
+        if name.ends_with(".ldb") || name.ends_with(".sst") {
+            if let Ok(mut table) = TableReader::open(&path)
+                && let Ok(Some(record)) = table.get(OBFUSCATE_KEY_KEY)
+            {
+                rows.push((record.seq, record.value));
+            }
+        } else if name.ends_with(".log")
+            && let Ok(log) = log_parser::parse_file(&path)
+        {
+            rows.extend(
+                log.batches
+                    .iter()
+                    .flat_map(|batch| batch.records.iter())
+                    .filter(|record| record.key == OBFUSCATE_KEY_KEY)
+                    .map(|record| (record.seq, record.value.clone())),
+            );
+        }
+    }
+
+    let (_, value) = rows.into_iter().max_by_key(|(seq, _)| *seq)?;
+    parse_obfuscate_key(&value?)
+}
+
+fn decode_chainstate_row(ctx: &DecodeContext, obfuscate_key: &[u8]) -> (String, String, String) {
+    let (k, v) = decode_chainstate_kv(ctx.key, ctx.value);
+    if ctx.key == OBFUSCATE_KEY_KEY {
+        return (k, v, "BC".to_string());
+    }
+
+    let v = match ctx.value {
+        Some(v_bytes) => {
+            let plain = deobfuscate(v_bytes, obfuscate_key);
+            if decode_coin_key(ctx.key).is_some()
+                && let Some(coin) = decode_coin(&plain, network_from_path(ctx.path))
+            {
+                coin_to_string(&coin)
+            } else if ctx.key == BEST_BLOCK_KEY && plain.len() == 32 {
+                hash_to_string(&plain)
+            } else {
+                decoder::bytes_to_hex(&plain)
+            }
+        }
+        None => v,
+    };
+    (k, v, "BC".to_string())
+}
+
+// -----------------------------------------------------------------------------
+// MSB base-128 VARINT of Bitcoin Core serialize.h (not LEB128)
+fn read_varint(bytes: &[u8], i: &mut usize) -> Option<u64> {
+    let mut n: u64 = 0;
+    loop {
+        let b = *bytes.get(*i)?;
+        *i += 1;
+        n = n.checked_mul(128)? | (b & 0x7F) as u64;
+        if b & 0x80 == 0 {
+            return Some(n);
+        }
+        n = n.checked_add(1)?;
+    }
+}
+
+fn read_compact_size(bytes: &[u8], i: &mut usize) -> Option<u64> {
+    let first = *bytes.get(*i)?;
+    *i += 1;
+    let size = match first {
+        0xFD => 2,
+        0xFE => 4,
+        0xFF => 8,
+        _ => return Some(first as u64),
+    };
+    let data = bytes.get(*i..*i + size)?;
+    *i += size;
+    let mut buf = [0u8; 8];
+    buf[..size].copy_from_slice(data);
+    Some(u64::from_le_bytes(buf))
+}
+
+// compressor.cpp DecompressAmount
+fn decompress_amount(x: u64) -> u64 {
+    if x == 0 {
+        return 0;
+    }
+    let mut x = x - 1;
+    let mut e = x % 10;
+    x /= 10;
+    let mut n = if e < 9 {
+        let d = (x % 9) + 1;
+        x /= 9;
+        x * 10 + d
+    } else {
+        x + 1
+    };
+    while e > 0 {
+        n = n.saturating_mul(10);
+        e -= 1;
+    }
+    n
+}
+
+// compressor.cpp ScriptCompression: special ids 0..=5, raw script otherwise
+fn decompress_script(bytes: &[u8], i: &mut usize, network: Network) -> Option<Script> {
+    let size = read_varint(bytes, i)?;
+    let (script_type, len) = match size {
+        0 => (ScriptType::P2pkh, 20),
+        1 => (ScriptType::P2sh, 20),
+        2..=5 => (ScriptType::P2pk, 32),
+        _ => (ScriptType::NonStandard, (size - 6) as usize),
+    };
+    let data = bytes.get(*i..i.checked_add(len)?)?;
+    *i += len;
+
+    let script = match size {
+        0 | 1 => Script {
+            script_type,
+            data: data.to_vec(),
+            address: Some(base58_address(script_type, data, network)),
+        },
+        2..=5 => {
+            // compressed public key, 4 / 5 were uncompressed keys in the script
+            let mut pubkey = vec![if size >= 4 {
+                size as u8 - 2
+            } else {
+                size as u8
+            }];
+            pubkey.extend_from_slice(data);
+            Script {
+                script_type,
+                data: pubkey,
+                address: None,
+            }
+        }
+        _ => classify_script(data, network),
+    };
+    Some(script)
+}
+
+fn classify_script(script: &[u8], network: Network) -> Script {
+    let (script_type, data, address) = match script {
+        // OP_DUP OP_HASH160 <20> OP_EQUALVERIFY OP_CHECKSIG
+        [0x76, 0xA9, 0x14, hash @ .., 0x88, 0xAC] if hash.len() == 20 => (
+            ScriptType::P2pkh,
+            hash,
+            Some(base58_address(ScriptType::P2pkh, hash, network)),
+        ),
+        // OP_HASH160 <20> OP_EQUAL
+        [0xA9, 0x14, hash @ .., 0x87] if hash.len() == 20 => (
+            ScriptType::P2sh,
+            hash,
+            Some(base58_address(ScriptType::P2sh, hash, network)),
+        ),
+        // <33|65 byte pubkey> OP_CHECKSIG
+        [0x21, pubkey @ .., 0xAC] if pubkey.len() == 33 => (ScriptType::P2pk, pubkey, None),
+        [0x41, pubkey @ .., 0xAC] if pubkey.len() == 65 => (ScriptType::P2pk, pubkey, None),
+        [0x6A, ..] => (ScriptType::OpReturn, script, None),
+        // OP_0..OP_16 <2..40 byte witness program>
+        [version @ (0x00 | 0x51..=0x60), len, program @ ..]
+            if *len as usize == program.len() && (2..=40).contains(&program.len()) =>
+        {
+            let witness_version = if *version == 0 { 0 } else { version - 0x50 };
+            let script_type = match (witness_version, program.len()) {
+                (0, 20) => ScriptType::P2wpkh,
+                (0, 32) => ScriptType::P2wsh,
+                (1, 32) => ScriptType::P2tr,
+                _ => ScriptType::WitnessUnknown,
+            };
+            (
+                script_type,
+                program,
+                segwit_address(witness_version, program, network),
+            )
+        }
+        _ => (ScriptType::NonStandard, script, None),
+    };
+
+    Script {
+        script_type,
+        data: data.to_vec(),
+        address,
+    }
+}
+
+fn hash_to_string(hash: &[u8]) -> String {
+    hash.iter().rev().map(|b| format!("{:02x}", b)).collect()
+}
+
+// Base58Check address of a P2PKH / P2SH hash
+fn base58_address(script_type: ScriptType, hash: &[u8], network: Network) -> String {
+    let version = match (script_type, network) {
+        (ScriptType::P2sh, Network::Main) => 0x05,
+        (ScriptType::P2sh, _) => 0xC4,
+        (_, Network::Main) => 0x00,
+        (_, _) => 0x6F,
+    };
+
+    let mut payload = vec![version];
+    payload.extend_from_slice(hash);
+    let checksum = Sha256::digest(Sha256::digest(&payload));
+    payload.extend_from_slice(&checksum[..4]);
+
+    // big number base conversion
+    let mut digits: Vec<u8> = Vec::new();
+    for &byte in &payload {
+        let mut carry = byte as u32;
+        for digit in &mut digits {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+
+    let leading_zeros = payload.iter().take_while(|&&b| b == 0).count();
+    std::iter::repeat_n(b'1', leading_zeros)
+        .chain(digits.iter().rev().map(|&d| BASE58_ALPHABET[d as usize]))
+        .map(char::from)
+        .collect()
+}
+
+// BIP173 (version 0) / BIP350 (version 1+) address of a witness program
+fn segwit_address(witness_version: u8, program: &[u8], network: Network) -> Option<String> {
+    let hrp: &[u8] = match network {
+        Network::Main => b"bc",
+        Network::Test => b"tb",
+        Network::Regtest => b"bcrt",
+    };
+
+    // 8 bit => 5 bit groups
+    let mut data = vec![witness_version];
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for &byte in program {
+        acc = (acc << 8) | byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            data.push(((acc >> bits) & 0x1F) as u8);
+        }
+    }
+    if bits > 0 {
+        data.push(((acc << (5 - bits)) & 0x1F) as u8);
+    }
+
+    let mut values: Vec<u8> = hrp.iter().map(|c| c >> 5).collect();
+    values.push(0);
+    values.extend(hrp.iter().map(|c| c & 0x1F));
+    values.extend_from_slice(&data);
+    values.extend_from_slice(&[0; 6]);
+    let checksum_const = if witness_version == 0 {
+        BECH32_CONST
+    } else {
+        BECH32M_CONST
+    };
+    let polymod = bech32_polymod(&values) ^ checksum_const;
+    data.extend((0..6).map(|i| ((polymod >> (5 * (5 - i))) & 0x1F) as u8));
+
+    let mut address = String::from_utf8(hrp.to_vec()).ok()?;
+    address.push('1');
+    address.extend(data.iter().map(|&d| BECH32_CHARSET[d as usize] as char));
+    Some(address)
+}
+
+fn bech32_polymod(values: &[u8]) -> u32 {
+    const GENERATOR: [u32; 5] = [
+        0x3b6a_57b2,
+        0x2650_8e6d,
+        0x1ea1_19fa,
+        0x3d42_33dd,
+        0x2a14_62b3,
+    ];
+    let mut chk = 1u32;
+    for &v in values {
+        let top = chk >> 25;
+        chk = ((chk & 0x1FF_FFFF) << 5) ^ v as u32;
+        for (i, g) in GENERATOR.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                chk ^= g;
+            }
+        }
+    }
+    chk
+}
+
+// -----------------------------------------------------------------------------
+pub mod export {
+    use super::*;
+
+    pub fn coins_csv_string(chainstate: &Chainstate) -> String {
+        let mut csv = String::new();
+        // Header
+        csv.push_str(
+            "\"Seq\",\"TxId\",\"Vout\",\"Height\",\"Coinbase\",\"Amount\",\"ScriptType\",\"Address\",\"Script\",\"St\",\"FP\"\n",
+        );
+
+        for record in &chainstate.coins {
+            let state_str = match record.state {
+                0 => "deleted",
+                1 => "live",
+                _ => "unknown",
+            };
+
+            let (height, coinbase, amount, script_type, address, script) = match &record.coin {
+                Some(coin) => (
+                    coin.height.to_string(),
+                    coin.coinbase.to_string(),
+                    format_btc(coin.amount),
+                    script_type_name(coin.script.script_type).to_string(),
+                    coin.script.address.clone().unwrap_or_default(),
+                    decoder::bytes_to_hex_raw(&coin.script.data)
+                        .trim_end()
+                        .to_string(),
+                ),
+                None => Default::default(),
+            };
+
+            csv.push_str(&format!(
+                "\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\"\n",
+                record.seq,
+                record.txid,
+                record.vout,
+                height,
+                coinbase,
+                amount,
+                script_type,
+                address,
+                script,
+                state_str,
+                record.file_path,
+            ));
+        }
+
+        csv
+    }
+
+    pub fn blocks_csv_string(block_index: &BlockIndex) -> String {
+        let mut csv = String::new();
+        // Header
+        csv.push_str(
+            "\"Seq\",\"Height\",\"Hash\",\"PrevHash\",\"Time\",\"Txs\",\"Status\",\"File\",\"DataPos\",\"St\",\"FP\"\n",
+        );
+
+        for entry in &block_index.blocks {
+            let state_str = match entry.state {
+                0 => "deleted",
+                1 => "live",
+                _ => "unknown",
+            };
+
+            csv.push_str(&format!(
+                "\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\"\n",
+                entry.seq,
+                entry.height,
+                entry.hash,
+                entry.prev_hash,
+                decoder::format_unix_timestamp(entry.time as i64),
+                entry.tx_count,
+                block_status_to_string(entry.status),
+                entry
+                    .file
+                    .map(|file| format!("blk{:05}.dat", file))
+                    .unwrap_or_default(),
+                entry
+                    .data_pos
+                    .map(|pos| pos.to_string())
+                    .unwrap_or_default(),
+                state_str,
+                entry.file_path,
+            ));
+        }
+
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn varint_examples_of_serialize_h() {
+        let cases: [(&[u8], u64); 8] = [
+            (&[0x00], 0),
+            (&[0x7F], 127),
+            (&[0x80, 0x00], 128),
+            (&[0x80, 0x7F], 255),
+            (&[0x81, 0x00], 256),
+            (&[0xFF, 0x7F], 16511),
+            (&[0x82, 0xFE, 0x7F], 65535),
+            (&[0x8E, 0xFE, 0xFE, 0xFF, 0x00], 1 << 32),
+        ];
+        for (bytes, expected) in cases {
+            let mut i = 0;
+            assert_eq!(read_varint(bytes, &mut i), Some(expected));
+            assert_eq!(i, bytes.len());
+        }
+    }
+
+    #[test]
+    fn varint_truncated() {
+        let mut i = 0;
+        assert_eq!(read_varint(&[0x80], &mut i), None);
+    }
+
+    #[test]
+    fn decompress_amount_examples_of_compressor_cpp() {
+        assert_eq!(decompress_amount(0x0), 0);
+        assert_eq!(decompress_amount(0x1), 1);
+        assert_eq!(decompress_amount(0x7), 1_000_000);
+        assert_eq!(decompress_amount(0x9), 100_000_000);
+        assert_eq!(decompress_amount(0x32), 5_000_000_000);
+        assert_eq!(decompress_amount(0x1406f40), 2_100_000_000_000_000);
+    }
+}
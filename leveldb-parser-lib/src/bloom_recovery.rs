@@ -0,0 +1,239 @@
+use std::fs;
+use std::io;
+
+use crate::database::DbRecord;
+use crate::decoder;
+use crate::ldb_parser::{self, BloomFilter};
+
+// the filter meta block of a table encodes the user keys of its data blocks,
+// testing candidate keys against a surviving filter shows which of them were
+// probably stored in a lost data range
+// -----------------------------------------------------------------------------
+pub struct FilterHit {
+    pub key: Vec<u8>,
+    pub filter_index: usize,
+    pub block_range: (u64, u64), // data block offsets covered by the filter
+    pub false_positive_rate: f64, // estimated for this filter
+}
+
+// -----------------------------------------------------------------------------
+// every (candidate, filter) pair that may match
+pub fn test_candidates<'a, I>(bloom_filter: &BloomFilter, candidates: I) -> Vec<FilterHit>
+where
+    I: IntoIterator<Item = &'a [u8]>,
+{
+    // filters whose block range does not fit into u64 offsets are skipped
+    let filters: Vec<_> = (0..bloom_filter.filter_offsets.len())
+        .filter_map(|index| {
+            let block_range = block_range(bloom_filter, index)?;
+            let filter = bloom_filter.filter_for_block(block_range.0)?;
+            // filters of ranges without data blocks hold no keys
+            (filter.len() >= 2).then(|| {
+                (
+                    index,
+                    filter,
+                    block_range,
+                    estimate_false_positive_rate(filter),
+                )
+            })
+        })
+        .collect();
+
+    let mut hits = Vec::new();
+    for key in candidates {
+        for &(index, filter, block_range, false_positive_rate) in &filters {
+            if ldb_parser::bloom_filter_may_match(filter, key) {
+                hits.push(FilterHit {
+                    key: key.to_vec(),
+                    filter_index: index,
+                    block_range,
+                    false_positive_rate,
+                });
+            }
+        }
+    }
+    hits
+}
+
+// probability of a random key matching: (share of set bits) ^ probes
+pub fn estimate_false_positive_rate(filter: &[u8]) -> f64 {
+    if filter.len() < 2 {
+        return 0.0;
+    }
+    let k = filter[filter.len() - 1];
+    if k > 30 {
+        return 1.0;
+    }
+    let bits = &filter[..filter.len() - 1];
+    let set_bits: u32 = bits.iter().map(|b| b.count_ones()).sum();
+    let share = set_bits as f64 / (bits.len() * 8) as f64;
+    share.powi(k as i32)
+}
+
+// Candidates ------------------------------------------------------------------
+
+// one candidate per line, line endings stripped
+pub fn candidates_from_wordlist(file_path: &str) -> io::Result<Vec<Vec<u8>>> {
+    let data = fs::read(file_path)?;
+    Ok(data
+        .split(|&b| b == b'\n')
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+        .filter(|line| !line.is_empty())
+        .map(|line| line.to_vec())
+        .collect())
+}
+
+// user keys of other files / databases, deduplicated
+pub fn candidates_from_records(records: &[DbRecord]) -> Vec<Vec<u8>> {
+    let mut keys: Vec<Vec<u8>> = records.iter().map(|record| record.key.clone()).collect();
+    keys.sort();
+    keys.dedup();
+    keys
+}
+
+// Local Storage keys of an origin: META:, METAACCESS: and, for the given
+// script keys, the Latin-1 and UTF-16LE encoded entry keys
+pub fn local_storage_candidates(origin: &str, keys: &[&str]) -> Vec<Vec<u8>> {
+    let mut candidates = vec![
+        format!("META:{}", origin).into_bytes(),
+        format!("METAACCESS:{}", origin).into_bytes(),
+    ];
+
+    for key in keys {
+        let mut prefix = format!("_{}", origin).into_bytes();
+        prefix.push(0x00);
+
+        if key.chars().all(|c| (c as u32) <= 0xFF) {
+            let mut latin1 = prefix.clone();
+            latin1.push(0x01);
+            latin1.extend(key.chars().map(|c| c as u8));
+            candidates.push(latin1);
+        }
+
+        let mut utf16 = prefix;
+        utf16.push(0x00);
+        utf16.extend(key.encode_utf16().flat_map(u16::to_le_bytes));
+        candidates.push(utf16);
+    }
+
+    candidates
+}
+
+// data block offsets covered by the filter at index, None on overflow
+fn block_range(bloom_filter: &BloomFilter, index: usize) -> Option<(u64, u64)> {
+    let range_len = 1u64.checked_shl(bloom_filter.base_log as u32)?;
+    let range_start = (index as u64).checked_mul(range_len)?;
+    Some((range_start, range_start.checked_add(range_len)?))
+}
+
+// -----------------------------------------------------------------------------
+pub mod export {
+    use super::*;
+
+    pub fn csv_string(hits: &[FilterHit]) -> String {
+        let mut csv = String::new();
+        // Header
+        csv.push_str("\"K\",\"KHex\",\"Filter\",\"BlockRange\",\"FPRate\"\n");
+
+        for hit in hits {
+            csv.push_str(&format!(
+                "\"{}\",\"{}\",\"{}\",\"{}-{}\",\"{:.6}\"\n",
+                decoder::bytes_to_utf8_lossy(&hit.key).replace("\"", "\"\""),
+                decoder::bytes_to_hex(&hit.key),
+                hit.filter_index,
+                hit.block_range.0,
+                hit.block_range.1,
+                hit.false_positive_rate,
+            ));
+        }
+
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // filter block with one filter per 2 KiB of data blocks
+    fn bloom_filter(filters: &[Vec<u8>]) -> BloomFilter {
+        let mut filter_data = Vec::new();
+        let mut filter_offsets = Vec::new();
+        for filter in filters {
+            filter_offsets.push(filter_data.len() as u32);
+            filter_data.extend(filter);
+        }
+        BloomFilter {
+            array_offset: filter_data.len() as u32,
+            filter_data,
+            base_log: 11,
+            filter_offsets,
+        }
+    }
+
+    fn keys(prefix: &str, count: usize) -> Vec<Vec<u8>> {
+        (0..count)
+            .map(|i| format!("{}{:05}", prefix, i).into_bytes())
+            .collect()
+    }
+
+    fn as_slices(keys: &[Vec<u8>]) -> Vec<&[u8]> {
+        keys.iter().map(Vec::as_slice).collect()
+    }
+
+    #[test]
+    fn stored_keys_hit_their_filter() {
+        let first = keys("first", 50);
+        let second = keys("second", 50);
+        let bloom_filter = bloom_filter(&[
+            ldb_parser::create_filter(&as_slices(&first), 10),
+            // range without data blocks
+            Vec::new(),
+            ldb_parser::create_filter(&as_slices(&second), 10),
+        ]);
+
+        let hits = test_candidates(&bloom_filter, first.iter().map(Vec::as_slice));
+        let first_hits: Vec<&FilterHit> = hits.iter().filter(|hit| hit.filter_index == 0).collect();
+        assert_eq!(first_hits.len(), first.len());
+        assert_eq!(first_hits[0].block_range, (0, 2048));
+        assert!(hits.iter().all(|hit| hit.filter_index != 1));
+
+        let hits = test_candidates(&bloom_filter, second.iter().map(Vec::as_slice));
+        assert_eq!(
+            hits.iter().filter(|hit| hit.filter_index == 2).count(),
+            second.len()
+        );
+        assert!(
+            hits.iter()
+                .filter(|hit| hit.filter_index == 2)
+                .all(|hit| hit.block_range == (4096, 6144))
+        );
+    }
+
+    #[test]
+    fn absent_keys_mostly_miss() {
+        let stored = keys("stored", 100);
+        let bloom_filter = bloom_filter(&[ldb_parser::create_filter(&as_slices(&stored), 10)]);
+
+        let absent = keys("absent", 1000);
+        let hits = test_candidates(&bloom_filter, absent.iter().map(Vec::as_slice));
+        // ~1% false positives at 10 bits per key
+        assert!(hits.len() < 50, "{} false positives", hits.len());
+    }
+
+    #[test]
+    fn false_positive_rate_from_set_bits() {
+        // half of the bits set, 2 probes
+        assert_eq!(estimate_false_positive_rate(&[0x0F, 0xF0, 2]), 0.25);
+        assert_eq!(estimate_false_positive_rate(&[0xFF, 0xFF, 6]), 1.0);
+        assert_eq!(estimate_false_positive_rate(&[0x00, 0x00, 6]), 0.0);
+        // reserved probe count matches everything
+        assert_eq!(estimate_false_positive_rate(&[0x00, 31]), 1.0);
+        assert_eq!(estimate_false_positive_rate(&[6]), 0.0);
+
+        let stored = keys("stored", 100);
+        let rate =
+            estimate_false_positive_rate(&ldb_parser::create_filter(&as_slices(&stored), 10));
+        assert!(rate > 0.001 && rate < 0.05, "rate {}", rate);
+    }
+}
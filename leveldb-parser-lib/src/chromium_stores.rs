@@ -0,0 +1,294 @@
+use crate::decoder;
+use crate::protobuf::{self, ProtoField, ProtoValue};
+
+// store specific decoding of Chromium profile databases that hold protobuf
+// values
+//   Sync Data/LevelDB (components/sync/model/model_type_store):
+//     <type>-dt-<id>          => entity data (specifics)
+//     <type>-md-<id>          => sync_pb::EntityMetadata
+//     <type>-GlobalMetadata   => sync_pb::ModelTypeState
+//   Site Characteristics Database (performance_manager site_data.proto):
+//     <origin>                => SiteDataProto
+//   shared_proto_db (components/leveldb_proto):
+//     <client>_<key>          => protobuf of the client
+// -----------------------------------------------------------------------------
+const SYNC_DATA_INFIX: &str = "-dt-";
+const SYNC_METADATA_INFIX: &str = "-md-";
+const SYNC_GLOBAL_METADATA_SUFFIX: &str = "-GlobalMetadata";
+
+// Sync Data -------------------------------------------------------------------
+
+pub fn decode_sync_data_kv(key: &[u8], value: Option<&[u8]>) -> (String, String) {
+    let key_str = decoder::bytes_to_utf8_lossy(key);
+    let k = if let Some((model_type, id)) = key_str.split_once(SYNC_DATA_INFIX) {
+        format!("{} data {}", model_type, id)
+    } else if let Some((model_type, id)) = key_str.split_once(SYNC_METADATA_INFIX) {
+        format!("{} metadata {}", model_type, id)
+    } else if let Some(model_type) = key_str.strip_suffix(SYNC_GLOBAL_METADATA_SUFFIX) {
+        format!("{} global metadata", model_type)
+    } else {
+        key_str.clone()
+    };
+
+    let v = match value {
+        Some(v_bytes) if key_str.contains(SYNC_METADATA_INFIX) => {
+            match protobuf::decode_message(v_bytes) {
+                Some(fields) if !fields.is_empty() => entity_metadata_to_string(&fields),
+                _ => decoder::bytes_to_hex(v_bytes),
+            }
+        }
+        Some(v_bytes) => protobuf::decode_protobuf(v_bytes),
+        None => String::new(),
+    };
+    (k, v)
+}
+
+// EntityMetadata: 1 client_tag_hash, 2 server_id, 3 is_deleted,
+// 4 sequence_number, 5 acked_sequence_number, 6 server_version,
+// 7 creation_time, 8 modification_time (ms since the Unix epoch),
+// 9 specifics_hash
+fn entity_metadata_to_string(fields: &[ProtoField]) -> String {
+    let mut items = Vec::new();
+    if let Some(s) = string_field(fields, 1) {
+        items.push(format!("client tag hash: {}", s));
+    }
+    if let Some(s) = string_field(fields, 2) {
+        items.push(format!("server id: {}", s));
+    }
+    if let Some(v) = varint_field(fields, 3) {
+        items.push(format!("deleted: {}", v != 0));
+    }
+    for (field_number, name) in [
+        (4, "sequence number"),
+        (5, "acked sequence number"),
+        (6, "server version"),
+    ] {
+        if let Some(v) = varint_field(fields, field_number) {
+            items.push(format!("{}: {}", name, v as i64));
+        }
+    }
+    for (field_number, name) in [(7, "created"), (8, "modified")] {
+        if let Some(v) = varint_field(fields, field_number) {
+            items.push(format!(
+                "{}: {}",
+                name,
+                decoder::format_unix_timestamp((v as i64).div_euclid(1000))
+            ));
+        }
+    }
+    if let Some(s) = string_field(fields, 9) {
+        items.push(format!("specifics hash: {}", s));
+    }
+
+    if items.is_empty() {
+        protobuf::fields_to_string(fields)
+    } else {
+        items.join(", ")
+    }
+}
+
+// Site Characteristics Database -----------------------------------------------
+
+pub fn decode_site_characteristics_kv(key: &[u8], value: Option<&[u8]>) -> (String, String) {
+    let k = decoder::bytes_to_utf8_lossy(key);
+    let v = match value {
+        Some(v_bytes) => match protobuf::decode_message(v_bytes) {
+            Some(fields) if !fields.is_empty() => site_data_to_string(&fields),
+            _ => decoder::bytes_to_hex(v_bytes),
+        },
+        None => String::new(),
+    };
+    (k, v)
+}
+
+// SiteDataProto: 1 last_loaded (s since the Unix epoch), 2..5 features
+// (SiteDataFeatureProto: 1 observation_duration in s, 2 use_timestamp in s
+// since the Unix epoch), 6 load_time_estimates
+fn site_data_to_string(fields: &[ProtoField]) -> String {
+    let mut items = Vec::new();
+    if let Some(v) = varint_field(fields, 1) {
+        items.push(format!(
+            "last loaded: {}",
+            decoder::format_unix_timestamp(v as i64)
+        ));
+    }
+    for (field_number, name) in [
+        (2, "updates favicon in background"),
+        (3, "updates title in background"),
+        (4, "uses audio in background"),
+        (5, "uses notifications in background"),
+    ] {
+        let Some(ProtoValue::Message(feature)) = protobuf::find_field(fields, field_number) else {
+            continue;
+        };
+        let mut feature_items = Vec::new();
+        if let Some(v) = varint_field(feature, 1) {
+            feature_items.push(format!("observed {} s", v as i64));
+        }
+        if let Some(v) = varint_field(feature, 2)
+            && v != 0
+        {
+            feature_items.push(format!("used {}", decoder::format_unix_timestamp(v as i64)));
+        }
+        items.push(format!("{}: {}", name, feature_items.join(" / ")));
+    }
+    if let Some(ProtoValue::Message(estimates)) = protobuf::find_field(fields, 6) {
+        items.push(format!(
+            "load time estimates: {}",
+            protobuf::fields_to_string(estimates)
+        ));
+    }
+
+    if items.is_empty() {
+        protobuf::fields_to_string(fields)
+    } else {
+        items.join(", ")
+    }
+}
+
+// shared_proto_db -------------------------------------------------------------
+
+// client prefix and key of the client, the prefix is the database name of the
+// client (e.g. "DownloadDB") without underscores
+pub fn split_shared_proto_db_key(key: &[u8]) -> Option<(String, String)> {
+    let key_str = decoder::bytes_to_utf8_lossy(key);
+    let (client, client_key) = key_str.split_once('_')?;
+    if client.is_empty() || !client.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return None;
+    }
+    Some((client.to_string(), client_key.to_string()))
+}
+
+pub fn decode_shared_proto_db_kv(key: &[u8], value: Option<&[u8]>) -> (String, String) {
+    let k = match split_shared_proto_db_key(key) {
+        Some((client, client_key)) => format!("[{}] {}", client, client_key),
+        None => decoder::bytes_to_utf8_lossy(key),
+    };
+    let v = match value {
+        Some(v_bytes) => protobuf::decode_protobuf(v_bytes),
+        None => String::new(),
+    };
+    (k, v)
+}
+
+// -----------------------------------------------------------------------------
+fn varint_field(fields: &[ProtoField], field_number: u64) -> Option<u64> {
+    match protobuf::find_field(fields, field_number)? {
+        ProtoValue::Varint(v) => Some(*v),
+        _ => None,
+    }
+}
+
+fn string_field(fields: &[ProtoField], field_number: u64) -> Option<String> {
+    match protobuf::find_field(fields, field_number)? {
+        ProtoValue::String(s) => Some(s.clone()),
+        ProtoValue::Bytes(bytes) => Some(decoder::bytes_to_hex(bytes)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn varint(mut value: u64) -> Vec<u8> {
+        let mut out = Vec::new();
+        while value >= 0x80 {
+            out.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        out.push(value as u8);
+        out
+    }
+
+    fn encode_varint_field(field_number: u64, value: u64) -> Vec<u8> {
+        [varint(field_number << 3), varint(value)].concat()
+    }
+
+    fn encode_bytes_field(field_number: u64, bytes: &[u8]) -> Vec<u8> {
+        [
+            varint((field_number << 3) | 2),
+            varint(bytes.len() as u64),
+            bytes.to_vec(),
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn sync_data_keys() {
+        let (k, _) = decode_sync_data_kv(b"bookmarks-dt-abc123", None);
+        assert_eq!(k, "bookmarks data abc123");
+        let (k, _) = decode_sync_data_kv(b"bookmarks-md-abc123", None);
+        assert_eq!(k, "bookmarks metadata abc123");
+        let (k, _) = decode_sync_data_kv(b"bookmarks-GlobalMetadata", None);
+        assert_eq!(k, "bookmarks global metadata");
+        let (k, v) = decode_sync_data_kv(b"other", None);
+        assert_eq!((k.as_str(), v.as_str()), ("other", ""));
+    }
+
+    #[test]
+    fn sync_entity_metadata_fields() {
+        let value = [
+            encode_bytes_field(1, b"hashABC="),
+            encode_bytes_field(2, b"server-1"),
+            encode_varint_field(3, 1),
+            encode_varint_field(4, 7),
+            encode_varint_field(6, 42),
+            // ms since the Unix epoch
+            encode_varint_field(7, 1_700_000_000_000),
+            encode_varint_field(8, 1_700_000_001_500),
+        ]
+        .concat();
+
+        let (_, v) = decode_sync_data_kv(b"bookmarks-md-abc123", Some(&value));
+        assert_eq!(
+            v,
+            "client tag hash: hashABC=, server id: server-1, deleted: true, \
+             sequence number: 7, server version: 42, \
+             created: 2023-11-14T22:13:20Z, modified: 2023-11-14T22:13:21Z"
+        );
+
+        // not a message => hex
+        let (_, v) = decode_sync_data_kv(b"bookmarks-md-abc123", Some(&[0xFF]));
+        assert_eq!(v, "\\xFF");
+    }
+
+    #[test]
+    fn site_data_fields() {
+        let feature = [
+            encode_varint_field(1, 3600),
+            encode_varint_field(2, 1_700_000_000),
+        ]
+        .concat();
+        let unused_feature = [encode_varint_field(1, 60), encode_varint_field(2, 0)].concat();
+        let value = [
+            encode_varint_field(1, 1_700_000_000),
+            encode_bytes_field(2, &feature),
+            encode_bytes_field(4, &unused_feature),
+        ]
+        .concat();
+
+        let (k, v) = decode_site_characteristics_kv(b"https://example.com", Some(&value));
+        assert_eq!(k, "https://example.com");
+        assert_eq!(
+            v,
+            "last loaded: 2023-11-14T22:13:20Z, \
+             updates favicon in background: observed 3600 s / used 2023-11-14T22:13:20Z, \
+             uses audio in background: observed 60 s"
+        );
+    }
+
+    #[test]
+    fn shared_proto_db_client_prefix() {
+        assert_eq!(
+            split_shared_proto_db_key(b"DownloadDB_guid_1"),
+            Some(("DownloadDB".to_string(), "guid_1".to_string()))
+        );
+        assert_eq!(split_shared_proto_db_key(b"_key"), None);
+        assert_eq!(split_shared_proto_db_key(b"no-client_key"), None);
+        assert_eq!(split_shared_proto_db_key(b"noseparator"), None);
+
+        let (k, _) = decode_shared_proto_db_kv(b"DownloadDB_guid_1", None);
+        assert_eq!(k, "[DownloadDB] guid_1");
+    }
+}
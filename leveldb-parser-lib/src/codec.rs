@@ -0,0 +1,316 @@
+use std::io::{self, Cursor, Read};
+use std::path::Path;
+
+use flate2::read::{DeflateDecoder, ZlibDecoder};
+
+use crate::utils;
+
+// -----------------------------------------------------------------------------
+pub trait BlockCodec: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn decompress(&self, data: &[u8]) -> io::Result<Vec<u8>>;
+}
+
+// built-in codecs
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Snappy,
+    Zstd,
+    Zlib,
+    RawDeflate,
+    Lz4, // RocksDB LZ4 block: varint decompressed size + raw LZ4 block
+}
+
+impl Compression {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "none" => Some(Compression::None),
+            "snappy" => Some(Compression::Snappy),
+            "zstd" => Some(Compression::Zstd),
+            "zlib" => Some(Compression::Zlib),
+            "deflate" | "rawdeflate" => Some(Compression::RawDeflate),
+            "lz4" => Some(Compression::Lz4),
+            _ => None,
+        }
+    }
+}
+
+impl BlockCodec for Compression {
+    fn name(&self) -> &str {
+        match self {
+            Compression::None => "NoCompression",
+            Compression::Snappy => "Snappy",
+            Compression::Zstd => "Zstd",
+            Compression::Zlib => "Zlib",
+            Compression::RawDeflate => "RawDeflate",
+            Compression::Lz4 => "LZ4",
+        }
+    }
+
+    fn decompress(&self, data: &[u8]) -> io::Result<Vec<u8>> {
+        let mut decompressed = Vec::new();
+        match self {
+            Compression::None => decompressed.extend_from_slice(data),
+            Compression::Snappy => {
+                decompressed = snap::raw::Decoder::new().decompress_vec(data)?;
+            }
+            Compression::Zstd => decompressed = zstd::decode_all(data)?,
+            Compression::Zlib => {
+                ZlibDecoder::new(data).read_to_end(&mut decompressed)?;
+            }
+            Compression::RawDeflate => {
+                DeflateDecoder::new(data).read_to_end(&mut decompressed)?;
+            }
+            Compression::Lz4 => decompressed = decompress_lz4_block(data)?,
+        }
+        Ok(decompressed)
+    }
+}
+
+// blocks of RocksDB format_version >= 2
+fn decompress_lz4_block(data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut cursor = Cursor::new(data);
+    let size = utils::read_varint(&mut cursor)? as usize;
+    let compressed = &data[cursor.position() as usize..];
+    // LZ4 expands at most ~255 times, a larger size is corrupt
+    if size > compressed.len().saturating_mul(255) + 16 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("LZ4: implausible decompressed size {}", size),
+        ));
+    }
+    lz4_flex::block::decompress(compressed, size)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("LZ4: {}", e)))
+}
+
+// -----------------------------------------------------------------------------
+// compression type byte of a table block => codec, forks of LevelDB assign
+// the ids differently
+pub struct CodecRegistry {
+    codecs: Vec<(u8, Box<dyn BlockCodec>)>,
+}
+
+impl CodecRegistry {
+    // no codecs, every block fails to decode
+    pub fn empty() -> Self {
+        CodecRegistry { codecs: Vec::new() }
+    }
+
+    // Google LevelDB (id 2 is Zstd since 1.23)
+    pub fn leveldb() -> Self {
+        Self::from_builtins(&[
+            (0x0, Compression::None),
+            (0x1, Compression::Snappy),
+            (0x2, Compression::Zstd),
+        ])
+    }
+
+    // Mojang LevelDB fork of Minecraft Bedrock worlds
+    pub fn bedrock() -> Self {
+        Self::from_builtins(&[
+            (0x0, Compression::None),
+            (0x1, Compression::Snappy),
+            (0x2, Compression::Zlib),
+            (0x4, Compression::RawDeflate),
+        ])
+    }
+
+    // Bedrock codecs for files of a Minecraft world, LevelDB codecs otherwise
+    pub fn for_path(file_path: &str) -> Self {
+        if crate::bedrock::is_world_db_file(Path::new(file_path)) {
+            Self::bedrock()
+        } else {
+            Self::leveldb()
+        }
+    }
+
+    // preset name ("leveldb", "bedrock") or comma separated "<id>=<codec>"
+    // list, e.g. "0=none,1=snappy,2=zlib,4=deflate"
+    pub fn from_spec(spec: &str) -> Result<Self, String> {
+        match spec.to_ascii_lowercase().as_str() {
+            "leveldb" => return Ok(Self::leveldb()),
+            "bedrock" => return Ok(Self::bedrock()),
+            _ => {}
+        }
+
+        let mut registry = Self::empty();
+        for item in spec
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+        {
+            let (id, name) = item.split_once('=').ok_or_else(|| {
+                format!("Invalid codec mapping '{}', expected <id>=<codec>", item)
+            })?;
+            let id: u8 = id
+                .trim()
+                .parse()
+                .map_err(|_| format!("Invalid compression id '{}'", id.trim()))?;
+            let compression = Compression::from_name(name.trim())
+                .ok_or_else(|| format!("Unknown codec '{}'", name.trim()))?;
+            registry.register(id, Box::new(compression));
+        }
+        Ok(registry)
+    }
+
+    // replaces the codec of an id
+    pub fn register(&mut self, id: u8, codec: Box<dyn BlockCodec>) {
+        self.codecs.retain(|(mapped_id, _)| *mapped_id != id);
+        self.codecs.push((id, codec));
+    }
+
+    pub fn get(&self, id: u8) -> Option<&dyn BlockCodec> {
+        self.codecs
+            .iter()
+            .find(|(mapped_id, _)| *mapped_id == id)
+            .map(|(_, codec)| codec.as_ref())
+    }
+
+    // unmapped ids are an error instead of passing the compressed bytes on
+    pub fn decompress(&self, id: u8, data: &[u8]) -> io::Result<Vec<u8>> {
+        match self.get(id) {
+            Some(codec) => codec.decompress(data).map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{} decompression failed: {}", codec.name(), e),
+                )
+            }),
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unknown compression id: {}", id),
+            )),
+        }
+    }
+
+    fn from_builtins(codecs: &[(u8, Compression)]) -> Self {
+        let mut registry = Self::empty();
+        for &(id, compression) in codecs {
+            registry.register(id, Box::new(compression));
+        }
+        registry
+    }
+}
+
+impl Default for CodecRegistry {
+    fn default() -> Self {
+        Self::leveldb()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Write;
+
+    use flate2::write::{DeflateEncoder, ZlibEncoder};
+
+    const DATA: &[u8] = b"leveldb block leveldb block leveldb block leveldb block";
+
+    fn codec_names(registry: &CodecRegistry) -> Vec<(u8, String)> {
+        (0..=u8::MAX)
+            .filter_map(|id| registry.get(id).map(|codec| (id, codec.name().to_string())))
+            .collect()
+    }
+
+    #[test]
+    fn spec_with_codec_list() {
+        let registry =
+            CodecRegistry::from_spec(" 0=none, 1=Snappy,2=zlib,4=deflate,7=lz4 ,").unwrap();
+        assert_eq!(
+            codec_names(&registry),
+            vec![
+                (0, "NoCompression".to_string()),
+                (1, "Snappy".to_string()),
+                (2, "Zlib".to_string()),
+                (4, "RawDeflate".to_string()),
+                (7, "LZ4".to_string()),
+            ]
+        );
+
+        // later mappings of an id replace earlier ones
+        let registry = CodecRegistry::from_spec("2=zstd,2=zlib").unwrap();
+        assert_eq!(registry.get(2).unwrap().name(), "Zlib");
+    }
+
+    #[test]
+    fn spec_errors() {
+        assert_eq!(
+            CodecRegistry::from_spec("1=brotli").err(),
+            Some("Unknown codec 'brotli'".to_string())
+        );
+        assert_eq!(
+            CodecRegistry::from_spec("256=none").err(),
+            Some("Invalid compression id '256'".to_string())
+        );
+        assert_eq!(
+            CodecRegistry::from_spec("x=none").err(),
+            Some("Invalid compression id 'x'".to_string())
+        );
+        assert_eq!(
+            CodecRegistry::from_spec("snappy").err(),
+            Some("Invalid codec mapping 'snappy', expected <id>=<codec>".to_string())
+        );
+    }
+
+    #[test]
+    fn presets() {
+        assert_eq!(
+            codec_names(&CodecRegistry::from_spec("LevelDB").unwrap()),
+            vec![
+                (0, "NoCompression".to_string()),
+                (1, "Snappy".to_string()),
+                (2, "Zstd".to_string()),
+            ]
+        );
+        assert_eq!(
+            codec_names(&CodecRegistry::from_spec("bedrock").unwrap()),
+            vec![
+                (0, "NoCompression".to_string()),
+                (1, "Snappy".to_string()),
+                (2, "Zlib".to_string()),
+                (4, "RawDeflate".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn unmapped_id_is_an_error() {
+        let registry = CodecRegistry::leveldb();
+        let error = registry.decompress(4, DATA).unwrap_err();
+        assert_eq!(error.to_string(), "Unknown compression id: 4");
+    }
+
+    #[test]
+    fn zlib_and_raw_deflate_round_trip() {
+        let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(DATA).unwrap();
+        let zlib = encoder.finish().unwrap();
+
+        let mut encoder = DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(DATA).unwrap();
+        let deflate = encoder.finish().unwrap();
+
+        let registry = CodecRegistry::bedrock();
+        assert_eq!(registry.decompress(2, &zlib).unwrap(), DATA);
+        assert_eq!(registry.decompress(4, &deflate).unwrap(), DATA);
+        // zlib header is not raw deflate
+        assert!(registry.decompress(4, &zlib).is_err());
+    }
+
+    #[test]
+    fn lz4_block_round_trip() {
+        // varint decompressed size + raw LZ4 block
+        let mut block = vec![DATA.len() as u8];
+        block.extend(lz4_flex::block::compress(DATA));
+
+        assert_eq!(Compression::Lz4.decompress(&block).unwrap(), DATA);
+
+        // size far beyond what the compressed bytes can expand to
+        let mut implausible = vec![0xFF, 0xFF, 0xFF, 0x7F];
+        implausible.extend(lz4_flex::block::compress(DATA));
+        assert!(Compression::Lz4.decompress(&implausible).is_err());
+    }
+}
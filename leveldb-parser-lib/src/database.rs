@@ -0,0 +1,159 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::bedrock;
+use crate::decoder;
+use crate::ldb_parser;
+use crate::log_parser;
+
+// -----------------------------------------------------------------------------
+pub struct Database {
+    pub dir_path: String,
+    pub storage_kind: decoder::StorageKind,
+    pub log_files: Vec<DbFile<log_parser::LogFile>>,
+    pub ldb_files: Vec<DbFile<ldb_parser::LdbFile>>,
+    pub parse_errors: Vec<String>,
+}
+
+pub struct DbFile<T> {
+    pub file_name: String,
+    pub file_path: String,
+    pub file_no: Option<u64>,
+    pub parsed: T,
+}
+
+// record of a .log batch or .ldb data block with its provenance
+pub struct DbRecord {
+    pub seq: u64,
+    pub state: u8,
+    pub key: Vec<u8>,
+    pub value: Option<Vec<u8>>,
+    pub file_path: String,
+    pub block_offset: u64,
+    pub key_offset: u64,
+}
+
+// -----------------------------------------------------------------------------
+pub fn parse_dir(dir_path: &str) -> io::Result<Database> {
+    let mut paths: Vec<_> = fs::read_dir(dir_path)?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .collect();
+    paths.sort();
+
+    let mut log_files = Vec::new();
+    let mut ldb_files = Vec::new();
+    let mut parse_errors = Vec::new();
+
+    for path in paths {
+        let file_name = match path.file_name() {
+            Some(name) => name.to_string_lossy().to_string(),
+            None => continue,
+        };
+        let file_path = path.to_string_lossy().to_string();
+        let file_no = parse_file_no(&file_name);
+
+        if file_name.ends_with(".log") {
+            match log_parser::parse_file(&file_path) {
+                Ok(parsed) => log_files.push(DbFile {
+                    file_name,
+                    file_path,
+                    file_no,
+                    parsed,
+                }),
+                Err(e) => parse_errors.push(format!("{}: {}", file_path, e)),
+            }
+        } else if file_name.ends_with(".ldb") || file_name.ends_with(".sst") {
+            match ldb_parser::parse_file(&file_path) {
+                Ok(parsed) => ldb_files.push(DbFile {
+                    file_name,
+                    file_path,
+                    file_no,
+                    parsed,
+                }),
+                Err(e) => parse_errors.push(format!("{}: {}", file_path, e)),
+            }
+        }
+    }
+
+    // detect on the keys of all files, a single file may hold only a few keys
+    let storage_kind = if bedrock::is_world_db_dir(Path::new(dir_path)) {
+        decoder::StorageKind::MinecraftBedrock
+    } else {
+        decoder::detect_storage_kind_with_keys(
+            dir_path,
+            log_files
+                .iter()
+                .flat_map(|f| f.parsed.batches.iter())
+                .flat_map(|batch| batch.records.iter().map(|record| record.key.as_slice()))
+                .chain(
+                    ldb_files
+                        .iter()
+                        .flat_map(|f| f.parsed.data_blocks.iter())
+                        .flat_map(|block| block.records.iter().map(|record| record.key.as_slice())),
+                ),
+        )
+    };
+    for log_file in &mut log_files {
+        log_file.parsed.storage_kind = storage_kind;
+    }
+    for ldb_file in &mut ldb_files {
+        ldb_file.parsed.storage_kind = storage_kind;
+    }
+
+    Ok(Database {
+        dir_path: dir_path.to_string(),
+        storage_kind,
+        log_files,
+        ldb_files,
+        parse_errors,
+    })
+}
+
+// all records of the database, ordered by sequence number
+pub fn collect_records(db: &Database) -> Vec<DbRecord> {
+    let mut records = Vec::new();
+
+    for log_file in &db.log_files {
+        for batch in &log_file.parsed.batches {
+            for record in &batch.records {
+                records.push(DbRecord {
+                    seq: record.seq,
+                    state: record.state,
+                    key: record.key.clone(),
+                    value: record.value.clone(),
+                    file_path: log_file.file_path.clone(),
+                    block_offset: batch.offset,
+                    key_offset: record.key_offset,
+                });
+            }
+        }
+    }
+
+    for ldb_file in &db.ldb_files {
+        for data_block in &ldb_file.parsed.data_blocks {
+            for record in &data_block.records {
+                records.push(DbRecord {
+                    seq: record.seq,
+                    state: record.state,
+                    key: record.key.clone(),
+                    value: (record.state != 0).then(|| record.value.clone()),
+                    file_path: ldb_file.file_path.clone(),
+                    block_offset: data_block.block_handle.offset,
+                    key_offset: data_block.block_handle.offset + record.entry.key_offset,
+                });
+            }
+        }
+    }
+
+    records.sort_by_key(|record| record.seq);
+    records
+}
+
+// "000005.ldb" => 5
+pub(crate) fn parse_file_no(file_name: &str) -> Option<u64> {
+    let stem = Path::new(file_name).file_stem()?.to_str()?;
+    stem.parse().ok()
+}
@@ -1,41 +1,220 @@
 use chrono::{TimeZone, Utc};
 
+use crate::bedrock;
+use crate::bitcoin;
+use crate::chromium_stores;
+use crate::protobuf;
+use crate::session_storage;
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum StorageKind {
     SessionStorage,
     LocalStorage,
     IndexedDb,
+    ExtensionSettings,
+    SyncData,
+    GcmStore,
+    ServiceWorker,
+    SiteCharacteristics,
+    SharedProtoDb,
+    PlatformNotifications,
+    MinecraftBedrock,
+    BitcoinChainstate,
+    BitcoinBlockIndex,
+    Protobuf,
     Generic,
 }
 
+// other Chromium LevelDB stores with protobuf-encoded values
+const PROTOBUF_STORE_DIRS: [&str; 2] = ["download service", "feature engagement"];
+
 pub fn detect_storage_kind(path: &str) -> StorageKind {
-    let lower = path.to_ascii_lowercase();
+    let lower = path.to_ascii_lowercase().replace('\\', "/");
     match () {
         _ if lower.contains("local storage") => StorageKind::LocalStorage,
         _ if lower.contains("session storage") => StorageKind::SessionStorage,
         _ if lower.contains("indexeddb") => StorageKind::IndexedDb,
+        _ if lower.contains("local extension settings") => StorageKind::ExtensionSettings,
+        _ if lower.contains("sync data") => StorageKind::SyncData,
+        _ if lower.contains("gcm store") => StorageKind::GcmStore,
+        _ if lower.contains("service worker/database") => StorageKind::ServiceWorker,
+        _ if lower.contains("site characteristics database") => StorageKind::SiteCharacteristics,
+        _ if lower.contains("shared_proto_db") => StorageKind::SharedProtoDb,
+        _ if lower.contains("platform notifications") => StorageKind::PlatformNotifications,
+        _ if lower.contains("minecraftworlds") => StorageKind::MinecraftBedrock,
+        _ if lower.contains("chainstate") => StorageKind::BitcoinChainstate,
+        _ if lower.contains("blocks/index") => StorageKind::BitcoinBlockIndex,
+        _ if PROTOBUF_STORE_DIRS.iter().any(|dir| lower.contains(dir)) => StorageKind::Protobuf,
         _ => StorageKind::Generic,
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StorageKindGuess {
+    pub kind: StorageKind,
+    pub confidence: f64, // 0.0 ..= 1.0
+}
+
+// minimum confidence to use a content based guess if the path gives no hint
+const MIN_GUESS_CONFIDENCE: f64 = 0.5;
+// minimum confidence to overrule the path heuristic
+const STRONG_GUESS_CONFIDENCE: f64 = 0.9;
+
+// marker keys weigh more than ordinary entry keys when ranking the kinds
+const MARKER_KEY_WEIGHT: f64 = 10.0;
+const VERSION_KEY_WEIGHT: f64 = 2.0;
+const ENTRY_KEY_WEIGHT: f64 = 1.0;
+// confidence a marker key alone gives, the rest comes from the share of keys
+// of the kind, so one marker among foreign keys can't overrule the path
+const MARKER_CONFIDENCE: f64 = 0.5;
+
+// content based detection, falls back to the path heuristic
+pub fn detect_storage_kind_with_keys<'a, I>(path: &str, keys: I) -> StorageKind
+where
+    I: IntoIterator<Item = &'a [u8]>,
+{
+    let path_kind = detect_storage_kind(path);
+    let guess = guess_storage_kind(keys);
+
+    match path_kind {
+        StorageKind::Generic if guess.confidence >= MIN_GUESS_CONFIDENCE => guess.kind,
+        _ if guess.kind != path_kind && guess.confidence >= STRONG_GUESS_CONFIDENCE => guess.kind,
+        _ => path_kind,
+    }
+}
+
+// infer the storage kind from the user keys of a database
+pub fn guess_storage_kind<'a, I>(keys: I) -> StorageKindGuess
+where
+    I: IntoIterator<Item = &'a [u8]>,
+{
+    let mut key_count = 0usize;
+    // per kind: (weighted score, matching keys, marker key seen)
+    let mut scores = [
+        (StorageKind::SessionStorage, 0.0, 0usize, false),
+        (StorageKind::LocalStorage, 0.0, 0, false),
+        (StorageKind::IndexedDb, 0.0, 0, false),
+        (StorageKind::MinecraftBedrock, 0.0, 0, false),
+        (StorageKind::BitcoinChainstate, 0.0, 0, false),
+    ];
+
+    for key in keys {
+        key_count += 1;
+        if let Some((kind, weight)) = classify_key(key)
+            && let Some(entry) = scores.iter_mut().find(|entry| entry.0 == kind)
+        {
+            entry.1 += weight;
+            entry.2 += 1;
+            entry.3 |= weight >= MARKER_KEY_WEIGHT;
+        }
+    }
+
+    let (kind, score, matched, marker) =
+        scores
+            .into_iter()
+            .fold((StorageKind::Generic, 0.0, 0, false), |best, candidate| {
+                if candidate.1 > best.1 {
+                    candidate
+                } else {
+                    best
+                }
+            });
+
+    if key_count == 0 || score == 0.0 {
+        return StorageKindGuess {
+            kind: StorageKind::Generic,
+            confidence: 0.0,
+        };
+    }
+
+    let share = matched as f64 / key_count as f64;
+    let confidence = if marker {
+        MARKER_CONFIDENCE + (1.0 - MARKER_CONFIDENCE) * share
+    } else {
+        share
+    };
+
+    StorageKindGuess {
+        kind,
+        confidence: confidence.min(1.0),
+    }
+}
+
+fn classify_key(key: &[u8]) -> Option<(StorageKind, f64)> {
+    // Local Storage
+    if key.starts_with(b"META:") || key.starts_with(b"METAACCESS:") {
+        return Some((StorageKind::LocalStorage, MARKER_KEY_WEIGHT));
+    }
+    if key == b"VERSION" {
+        return Some((StorageKind::LocalStorage, VERSION_KEY_WEIGHT));
+    }
+    if let Some(rest) = key.strip_prefix(b"_")
+        && let Some(delim_pos) = rest.iter().position(|&b| b == 0x00)
+        && rest[..delim_pos].windows(3).any(|w| w == b"://")
+        && matches!(rest.get(delim_pos + 1), Some(0x00 | 0x01))
+    {
+        return Some((StorageKind::LocalStorage, ENTRY_KEY_WEIGHT));
+    }
+
+    // Session Storage
+    if key.starts_with(b"namespace-") || key == b"next-map-id" {
+        return Some((StorageKind::SessionStorage, MARKER_KEY_WEIGHT));
+    }
+    if key == b"version" {
+        return Some((StorageKind::SessionStorage, VERSION_KEY_WEIGHT));
+    }
+    if let Some(rest) = key.strip_prefix(b"map-")
+        && let Some(delim_pos) = rest.iter().position(|&b| b == b'-')
+        && delim_pos > 0
+        && rest[..delim_pos].iter().all(u8::is_ascii_digit)
+    {
+        return Some((StorageKind::SessionStorage, ENTRY_KEY_WEIGHT));
+    }
+
+    // Bitcoin Core chainstate
+    if key == bitcoin::OBFUSCATE_KEY_KEY {
+        return Some((StorageKind::BitcoinChainstate, MARKER_KEY_WEIGHT));
+    }
+
+    // Minecraft Bedrock
+    if bedrock::is_marker_key(key) {
+        return Some((StorageKind::MinecraftBedrock, MARKER_KEY_WEIGHT));
+    }
+
+    // IndexedDB: schema / data version of the global metadata
+    if key == [0x00, 0x00, 0x00, 0x00, 0x00] || key == [0x00, 0x00, 0x00, 0x00, 0x02] {
+        return Some((StorageKind::IndexedDb, MARKER_KEY_WEIGHT));
+    }
+    if let Some((prefix, prefix_len)) = decode_indexeddb_key_prefix(key)
+        && is_indexeddb_key(key, prefix, prefix_len)
+    {
+        return Some((StorageKind::IndexedDb, ENTRY_KEY_WEIGHT));
+    }
+
+    // Minecraft Bedrock chunk records
+    bedrock::decode_chunk_key(key).map(|_| (StorageKind::MinecraftBedrock, ENTRY_KEY_WEIGHT))
+}
+
+fn is_indexeddb_key(key: &[u8], prefix: IndexedDbKeyPrefix, prefix_len: usize) -> bool {
+    let payload = &key[prefix_len..];
+    match prefix.key_type() {
+        IndexedDbKeyType::GlobalMetadata | IndexedDbKeyType::DatabaseMetadata => {
+            key[0] == 0x00 && !payload.is_empty()
+        }
+        IndexedDbKeyType::ObjectStoreData
+        | IndexedDbKeyType::ExistsEntry
+        | IndexedDbKeyType::BlobEntry => {
+            matches!(decode_indexeddb_key_at(payload), Some((_, len)) if len == payload.len())
+        }
+        IndexedDbKeyType::IndexData => decode_indexeddb_key_at(payload).is_some(),
+        IndexedDbKeyType::Invalid => false,
+    }
+}
+
 pub fn decode_kv(kind: StorageKind, key: &[u8], value: Option<&[u8]>) -> (String, String, String) {
     match kind {
         StorageKind::SessionStorage => {
-            let k = bytes_to_utf8_lossy(key);
-            let v = match value {
-                Some(v_bytes) => {
-                    if k.starts_with("map-") {
-                        if let Some(s) = try_utf16le(v_bytes) {
-                            s
-                        } else {
-                            bytes_to_utf8_lossy(v_bytes)
-                        }
-                    } else {
-                        bytes_to_utf8_lossy(v_bytes)
-                    }
-                }
-                None => String::new(),
-            };
+            let (k, v) = session_storage::decode_session_storage_kv(key, value);
             (k, v, "S".to_string())
         }
         StorageKind::LocalStorage => {
@@ -65,26 +244,120 @@ pub fn decode_kv(kind: StorageKind, key: &[u8], value: Option<&[u8]>) -> (String
             (k, v, "L".to_string())
         }
         StorageKind::IndexedDb => {
-            match key {
-                // record entry
-                [_, _, _, 0x01, entry_type @ 0x00..=0x06, key_payload @ ..] => {
-                    let k = decode_indexeddb_key(*entry_type, key_payload);
+            let (prefix, prefix_len) = match decode_indexeddb_key_prefix(key) {
+                Some(p) => p,
+                None => return decode_indexeddb_raw(key, value),
+            };
+            let key_payload = &key[prefix_len..];
+
+            match prefix.key_type() {
+                IndexedDbKeyType::ObjectStoreData => {
+                    let k = decode_indexeddb_key(key_payload);
                     let v = match value {
                         Some(v_bytes) => decode_indexeddb_entry(v_bytes),
                         None => String::new(),
                     };
                     (k, v, "IE".to_string())
                 }
-                _ => {
-                    let k = bytes_to_hex(key);
+                IndexedDbKeyType::IndexData => {
+                    let k = decode_indexeddb_index_key(key_payload);
                     let v = match value {
-                        Some(v_bytes) => bytes_to_hex(v_bytes),
+                        Some(v_bytes) => decode_indexeddb_index_value(v_bytes),
+                        None => String::new(),
+                    };
+                    (k, v, "II".to_string())
+                }
+                IndexedDbKeyType::ExistsEntry => {
+                    let k = decode_indexeddb_key(key_payload);
+                    let v = match value {
+                        Some(v_bytes) => decode_indexeddb_version(v_bytes),
+                        None => String::new(),
+                    };
+                    (k, v, "I".to_string())
+                }
+                IndexedDbKeyType::BlobEntry => {
+                    let k = decode_indexeddb_key(key_payload);
+                    let v = match value {
+                        Some(v_bytes) => decode_indexeddb_blob_infos(v_bytes),
                         None => String::new(),
                     };
                     (k, v, "I".to_string())
                 }
+                _ => decode_indexeddb_raw(key, value),
             }
         }
+        StorageKind::ExtensionSettings => {
+            // storage.local: plain string keys, JSON values
+            let k = bytes_to_utf8_lossy(key);
+            let v = match value {
+                Some(v_bytes) => match serde_json::from_slice::<serde_json::Value>(v_bytes) {
+                    Ok(json) => json.to_string(),
+                    Err(_) => bytes_to_utf8_lossy(v_bytes),
+                },
+                None => String::new(),
+            };
+            (k, v, "X".to_string())
+        }
+        StorageKind::SyncData => {
+            let (k, v) = chromium_stores::decode_sync_data_kv(key, value);
+            (k, v, "SY".to_string())
+        }
+        StorageKind::GcmStore => {
+            let k = bytes_to_utf8_lossy(key);
+            let v = match value {
+                Some(v_bytes) => decode_gcm_store_value(key, v_bytes),
+                None => String::new(),
+            };
+            (k, v, "GC".to_string())
+        }
+        StorageKind::ServiceWorker => {
+            let k = bytes_to_ascii_with_hex(key);
+            let v = match value {
+                Some(v_bytes) => decode_text_or_protobuf(v_bytes),
+                None => String::new(),
+            };
+            (k, v, "SW".to_string())
+        }
+        StorageKind::SiteCharacteristics => {
+            let (k, v) = chromium_stores::decode_site_characteristics_kv(key, value);
+            (k, v, "SC".to_string())
+        }
+        StorageKind::SharedProtoDb => {
+            let (k, v) = chromium_stores::decode_shared_proto_db_kv(key, value);
+            (k, v, "SP".to_string())
+        }
+        StorageKind::PlatformNotifications => {
+            let k = bytes_to_ascii_with_hex(key);
+            let v = match value {
+                Some(v_bytes) => decode_text_or_protobuf(v_bytes),
+                None => String::new(),
+            };
+            (k, v, "PN".to_string())
+        }
+        StorageKind::MinecraftBedrock => {
+            let k = bedrock::decode_key(key);
+            let v = match value {
+                Some(v_bytes) => bedrock::decode_value(key, v_bytes),
+                None => String::new(),
+            };
+            (k, v, "MC".to_string())
+        }
+        StorageKind::BitcoinChainstate => {
+            let (k, v) = bitcoin::decode_chainstate_kv(key, value);
+            (k, v, "BC".to_string())
+        }
+        StorageKind::BitcoinBlockIndex => {
+            let (k, v) = bitcoin::decode_block_index_kv(key, value);
+            (k, v, "BB".to_string())
+        }
+        StorageKind::Protobuf => {
+            let k = bytes_to_utf8_lossy(key);
+            let v = match value {
+                Some(v_bytes) => protobuf::decode_protobuf(v_bytes),
+                None => String::new(),
+            };
+            (k, v, "P".to_string())
+        }
         StorageKind::Generic => {
             let k = bytes_to_utf8_lossy(key);
             let v = match value {
@@ -134,7 +407,8 @@ fn decode_local_storage_key(bytes: &[u8]) -> String {
     }
 }
 
-fn decode_local_storage_value(bytes: &[u8]) -> String {
+// encoding flag (0x00: UTF-16LE, 0x01: Latin-1) followed by the string
+pub fn decode_local_storage_value(bytes: &[u8]) -> String {
     if bytes.starts_with(&[0x00]) || bytes.starts_with(&[0x01]) {
         let encoding_flag = bytes[0];
         let value_entry = &bytes[1..];
@@ -165,54 +439,11 @@ fn decode_local_storage_value(bytes: &[u8]) -> String {
 }
 
 fn decode_local_storage_meta(v_bytes: &[u8]) -> String {
-    // Chrome timestamp epoch: 1601-01-01T00:00:00Z
-    const CHROME_EPOCH: i64 = 11644473600000000; // microseconds between 1601-01-01 and 1970-01-01
-
-    // parse protobuf: field 1 = creation_time (varint, microseconds since 1601)
-    // optionally, field 2 = size (varint)
-    let mut i = 0;
-    let mut creation_time: Option<u64> = None;
-    let mut size: Option<u64> = None;
-
-    while i < v_bytes.len() {
-        let key = v_bytes[i];
-        i += 1;
-        let field_number = key >> 3;
-        let wire_type = key & 0x07;
-
-        match (field_number, wire_type) {
-            (1, 0) => {
-                // varint: creation_time
-                let (val, consumed) = parse_varint(&v_bytes[i..]);
-                creation_time = Some(val);
-                i += consumed;
-            }
-            (2, 0) => {
-                // varint: size
-                let (val, consumed) = parse_varint(&v_bytes[i..]);
-                size = Some(val);
-                i += consumed;
-            }
-            _ => {
-                // unknown field
-                break;
-            }
-        }
-    }
+    let (creation_time, size) = parse_local_storage_meta(v_bytes);
 
     let mut out = String::new();
     if let Some(ts) = creation_time {
-        // convert Chrome timestamp to Unix timestamp
-        let unix_us = ts as i64 - CHROME_EPOCH;
-        let unix_s = unix_us / 1_000_000;
-        let unix_ns = (unix_us % 1_000_000) * 1000;
-
-        let dt = Utc.timestamp_opt(unix_s, unix_ns as u32).single();
-        if let Some(dt) = dt {
-            out.push_str(&dt.to_rfc3339_opts(chrono::SecondsFormat::Millis, true));
-        } else {
-            out.push_str(&unix_s.to_string()); // fallback to Unix seconds
-        }
+        out.push_str(&format_chrome_timestamp(ts as i64));
     }
     if let Some(sz) = size {
         out.push_str(&format!(" ({})", sz));
@@ -223,21 +454,150 @@ fn decode_local_storage_meta(v_bytes: &[u8]) -> String {
     out
 }
 
+// META: / METAACCESS: values, returns (time, size)
+pub fn parse_local_storage_meta(v_bytes: &[u8]) -> (Option<u64>, Option<u64>) {
+    // protobuf: field 1 = creation_time (varint, microseconds since 1601)
+    // optionally, field 2 = size (varint)
+    let fields = protobuf::decode_message(v_bytes).unwrap_or_default();
+    let varint_field = |field_number| match protobuf::find_field(&fields, field_number) {
+        Some(protobuf::ProtoValue::Varint(val)) => Some(*val),
+        _ => None,
+    };
+
+    (varint_field(1), varint_field(2))
+}
+
+// Chromium stores --------------------------------------------------------------
+
+// GCM Store: strings (ids, tokens, times as decimal string) or protobuf messages
+fn decode_gcm_store_value(key: &[u8], v_bytes: &[u8]) -> String {
+    if key.ends_with(b"_time_key")
+        && let Some(ts) = std::str::from_utf8(v_bytes)
+            .ok()
+            .and_then(|s| s.parse::<i64>().ok())
+    {
+        return format!("{} ({})", ts, format_chrome_timestamp(ts));
+    }
+    decode_text_or_protobuf(v_bytes)
+}
+
+fn decode_text_or_protobuf(v_bytes: &[u8]) -> String {
+    match std::str::from_utf8(v_bytes) {
+        Ok(s) if !s.is_empty() && !s.chars().any(char::is_control) => s.to_string(),
+        _ => protobuf::decode_protobuf(v_bytes),
+    }
+}
+
 // IndexedDB -------------------------------------------------------------------
 
-fn decode_indexeddb_key(entry_type: u8, payload: &[u8]) -> String {
-    match decode_indexeddb_key_inner(entry_type, payload) {
+// special index ids of the KeyPrefix (see Chromium indexeddb/indexed_db_leveldb_coding.h)
+const INDEXEDDB_OBJECT_STORE_DATA_INDEX_ID: u64 = 1;
+const INDEXEDDB_EXISTS_ENTRY_INDEX_ID: u64 = 2;
+const INDEXEDDB_BLOB_ENTRY_INDEX_ID: u64 = 3;
+const INDEXEDDB_MINIMUM_INDEX_ID: u64 = 30;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IndexedDbKeyPrefix {
+    pub database_id: u64,
+    pub object_store_id: u64,
+    pub index_id: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IndexedDbKeyType {
+    GlobalMetadata,
+    DatabaseMetadata,
+    ObjectStoreData,
+    ExistsEntry,
+    BlobEntry,
+    IndexData,
+    Invalid,
+}
+
+impl IndexedDbKeyPrefix {
+    pub fn key_type(&self) -> IndexedDbKeyType {
+        match (self.database_id, self.object_store_id, self.index_id) {
+            (0, _, _) => IndexedDbKeyType::GlobalMetadata,
+            (_, 0, _) => IndexedDbKeyType::DatabaseMetadata,
+            (_, _, INDEXEDDB_OBJECT_STORE_DATA_INDEX_ID) => IndexedDbKeyType::ObjectStoreData,
+            (_, _, INDEXEDDB_EXISTS_ENTRY_INDEX_ID) => IndexedDbKeyType::ExistsEntry,
+            (_, _, INDEXEDDB_BLOB_ENTRY_INDEX_ID) => IndexedDbKeyType::BlobEntry,
+            (_, _, id) if id >= INDEXEDDB_MINIMUM_INDEX_ID => IndexedDbKeyType::IndexData,
+            _ => IndexedDbKeyType::Invalid,
+        }
+    }
+}
+
+// first byte encodes the byte lengths of the ids: 3 bits database id, 3 bits
+// object store id, 2 bits index id (each stored as length - 1), followed by
+// the little-endian ids; returns the prefix and the consumed byte count
+pub fn decode_indexeddb_key_prefix(bytes: &[u8]) -> Option<(IndexedDbKeyPrefix, usize)> {
+    let first_byte = *bytes.first()?;
+    let database_id_len = ((first_byte >> 5) & 0x07) as usize + 1;
+    let object_store_id_len = ((first_byte >> 2) & 0x07) as usize + 1;
+    let index_id_len = (first_byte & 0x03) as usize + 1;
+
+    let total_len = 1 + database_id_len + object_store_id_len + index_id_len;
+    if bytes.len() < total_len {
+        return None;
+    }
+
+    let read_le = |slice: &[u8]| {
+        slice
+            .iter()
+            .rev()
+            .fold(0u64, |acc, &b| (acc << 8) | b as u64)
+    };
+
+    let mut i = 1;
+    let database_id = read_le(&bytes[i..i + database_id_len]);
+    i += database_id_len;
+    let object_store_id = read_le(&bytes[i..i + object_store_id_len]);
+    i += object_store_id_len;
+    let index_id = read_le(&bytes[i..i + index_id_len]);
+
+    Some((
+        IndexedDbKeyPrefix {
+            database_id,
+            object_store_id,
+            index_id,
+        },
+        total_len,
+    ))
+}
+
+fn decode_indexeddb_raw(key: &[u8], value: Option<&[u8]>) -> (String, String, String) {
+    let k = bytes_to_hex(key);
+    let v = match value {
+        Some(v_bytes) => bytes_to_hex(v_bytes),
+        None => String::new(),
+    };
+    (k, v, "I".to_string())
+}
+
+// encoded IDB key: type byte followed by the type specific payload
+fn decode_indexeddb_key(bytes: &[u8]) -> String {
+    match decode_indexeddb_key_at(bytes) {
         Some((decoded, _)) => decoded,
-        None => bytes_to_hex(payload),
+        None => bytes_to_hex(bytes),
     }
 }
 
+fn decode_indexeddb_key_at(bytes: &[u8]) -> Option<(String, usize)> {
+    let (&entry_type, payload) = bytes.split_first()?;
+    let (decoded, consumed) = decode_indexeddb_key_inner(entry_type, payload)?;
+    Some((decoded, consumed + 1))
+}
+
 fn decode_indexeddb_key_inner(entry_type: u8, payload: &[u8]) -> Option<(String, usize)> {
     match entry_type {
+        0x00 => Some(("null".to_string(), 0)),
         0x01 => decode_indexeddb_key_string(payload),
         0x02 => decode_indexeddb_key_date(payload),
         0x03 => decode_indexeddb_key_number(payload),
         0x04 => decode_indexeddb_key_array(payload),
+        0x05 => Some(("MinKey".to_string(), 0)),
+        0x06 => decode_indexeddb_key_binary(payload),
         _ => None,
     }
 }
@@ -269,6 +629,15 @@ fn decode_indexeddb_key_number(payload: &[u8]) -> Option<(String, usize)> {
     Some((f64::from_le_bytes(arr).to_string(), 8))
 }
 
+fn decode_indexeddb_key_binary(payload: &[u8]) -> Option<(String, usize)> {
+    let (len, consumed) = parse_varint(payload);
+    if consumed == 0 || consumed + len as usize > payload.len() {
+        return None;
+    }
+    let data = &payload[consumed..consumed + len as usize];
+    Some((format!("<{}>", bytes_to_hex(data)), consumed + len as usize))
+}
+
 fn decode_indexeddb_key_array(payload: &[u8]) -> Option<(String, usize)> {
     let (count, mut offset) = parse_varint(payload);
     if offset == 0 {
@@ -288,7 +657,131 @@ fn decode_indexeddb_key_array(payload: &[u8]) -> Option<(String, usize)> {
     Some((format!("[{}]", items.join(", ")), offset))
 }
 
+// index data key: index key, sequence number (varint), primary key
+fn decode_indexeddb_index_key(bytes: &[u8]) -> String {
+    let decoded = (|| {
+        let (index_key, mut i) = decode_indexeddb_key_at(bytes)?;
+        let seq = read_varint_len(bytes, &mut i)?;
+        let (primary_key, _) = decode_indexeddb_key_at(&bytes[i..])?;
+        Some(format!("{} @ {} -> {}", index_key, seq, primary_key))
+    })();
+    decoded.unwrap_or_else(|| bytes_to_hex(bytes))
+}
+
+// index data value: version (varint), primary key
+fn decode_indexeddb_index_value(bytes: &[u8]) -> String {
+    let mut i = 0;
+    if read_varint_len(bytes, &mut i).is_none() {
+        return bytes_to_hex(bytes);
+    }
+    match decode_indexeddb_key_at(&bytes[i..]) {
+        Some((primary_key, _)) => primary_key,
+        None => bytes_to_hex(bytes),
+    }
+}
+
+// exists entry value: version (varint)
+fn decode_indexeddb_version(bytes: &[u8]) -> String {
+    let mut i = 0;
+    match read_varint_len(bytes, &mut i) {
+        Some(version) if i == bytes.len() => format!("Version: {}", version),
+        _ => bytes_to_hex(bytes),
+    }
+}
+
+// blob entry value: list of blob infos
+// is_file (bool), blob number (varint), type (string with length), then
+// for files: file name (string with length), last modified (varint),
+// for blobs: size (varint)
+fn decode_indexeddb_blob_infos(bytes: &[u8]) -> String {
+    let decoded = (|| {
+        let mut i = 0;
+        let mut infos = Vec::new();
+        while i < bytes.len() {
+            let is_file = match bytes[i] {
+                0x00 => false,
+                0x01 => true,
+                _ => return None,
+            };
+            i += 1;
+            let blob_no = read_varint_len(bytes, &mut i)?;
+            let (mime_type, consumed) = decode_indexeddb_key_string(&bytes[i..])?;
+            i += consumed;
+
+            if is_file {
+                let (file_name, consumed) = decode_indexeddb_key_string(&bytes[i..])?;
+                i += consumed;
+                let (last_modified, consumed) = parse_varint(&bytes[i..]);
+                if consumed == 0 {
+                    return None;
+                }
+                i += consumed;
+                infos.push(format!(
+                    "File: No.: {}, Type: {}, Name: {}, Modified: {}",
+                    blob_no,
+                    mime_type,
+                    file_name,
+                    format_chrome_timestamp(last_modified as i64)
+                ));
+            } else {
+                let size = read_varint_len(bytes, &mut i)?;
+                infos.push(format!(
+                    "Blob: No.: {}, Type: {}, Size: {} Bytes",
+                    blob_no, mime_type, size
+                ));
+            }
+        }
+        Some(infos.join("; "))
+    })();
+    decoded.unwrap_or_else(|| bytes_to_hex(bytes))
+}
+
+// Blink wraps large values (see Blink modules/indexeddb/idb_value_wrapping.cc):
+// version tag (0xFF), pseudo version (0x11) and a processing tag
+const BLINK_VERSION_TAG: u8 = 0xFF;
+const BLINK_REQUIRES_PROCESSING_PSEUDO_VERSION: u8 = 0x11;
+const BLINK_REPLACE_WITH_BLOB: u8 = 0x01;
+const BLINK_COMPRESSED_WITH_SNAPPY: u8 = 0x02;
+
 fn decode_indexeddb_entry(bytes: &[u8]) -> String {
+    // skip IndexedDB version
+    let mut i = 0;
+    if read_varint_len(bytes, &mut i).is_some() {
+        match &bytes[i..] {
+            [
+                BLINK_VERSION_TAG,
+                BLINK_REQUIRES_PROCESSING_PSEUDO_VERSION,
+                BLINK_COMPRESSED_WITH_SNAPPY,
+                payload @ ..,
+            ] => {
+                if let Ok(decompressed) = snap::raw::Decoder::new().decompress_vec(payload) {
+                    return decode_indexeddb_ssv(&decompressed);
+                }
+            }
+            [
+                BLINK_VERSION_TAG,
+                BLINK_REQUIRES_PROCESSING_PSEUDO_VERSION,
+                BLINK_REPLACE_WITH_BLOB,
+                payload @ ..,
+            ] => {
+                // value stored externally: blob size (varint), blob index (varint)
+                let mut j = 0;
+                if let (Some(size), Some(index)) = (
+                    read_varint_len(payload, &mut j),
+                    read_varint_len(payload, &mut j),
+                ) {
+                    return format!("Wrapped in Blob: Size: {} Bytes, Index: {}", size, index);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    decode_indexeddb_ssv(bytes)
+}
+
+// serialized script value: Blink header, V8 header, V8 payload
+fn decode_indexeddb_ssv(bytes: &[u8]) -> String {
     // find two 0xFF sentinels in header
     let mut i = 0;
     let mut ff = 0;
@@ -490,10 +983,10 @@ fn handle_tag_0x63(bytes: &[u8], i: &mut usize) -> Option<String> {
     }
     let payload = &bytes[*i..*i + len];
     *i += len;
-    if len % 2 == 0 {
-        if let Some(s) = try_utf16le(payload) {
-            return Some(format!("\"{}\"", s));
-        }
+    if len.is_multiple_of(2)
+        && let Some(s) = try_utf16le(payload)
+    {
+        return Some(format!("\"{}\"", s));
     }
     None
 }
@@ -554,6 +1047,28 @@ fn decode_date(payload: &[u8]) -> Option<String> {
 
 // -----------------------------------------------------------------------------
 
+// Chrome timestamp: microseconds since 1601-01-01T00:00:00Z
+pub fn format_chrome_timestamp(ts: i64) -> String {
+    const CHROME_EPOCH: i64 = 11644473600000000; // microseconds between 1601-01-01 and 1970-01-01
+
+    // convert Chrome timestamp to Unix timestamp
+    let unix_us = ts.saturating_sub(CHROME_EPOCH);
+    let unix_s = unix_us.div_euclid(1_000_000);
+    let unix_ns = unix_us.rem_euclid(1_000_000) * 1000;
+
+    match Utc.timestamp_opt(unix_s, unix_ns as u32).single() {
+        Some(dt) => dt.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        None => unix_s.to_string(), // fallback to Unix seconds
+    }
+}
+
+pub fn format_unix_timestamp(secs: i64) -> String {
+    match Utc.timestamp_opt(secs, 0).single() {
+        Some(dt) => dt.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        None => secs.to_string(),
+    }
+}
+
 pub fn bytes_to_hex(bytes: &[u8]) -> String {
     bytes.iter().map(|b| format!("\\x{:02X}", b)).collect()
 }
@@ -610,7 +1125,7 @@ pub fn bytes_to_utf8_lossy(bytes: &[u8]) -> String {
 }
 
 fn try_utf16(bytes: &[u8], little_endian: bool) -> Option<String> {
-    if bytes.len() >= 2 && bytes.len() % 2 == 0 {
+    if bytes.len() >= 2 && bytes.len().is_multiple_of(2) {
         let mut buf = Vec::with_capacity(bytes.len() / 2);
         for c in bytes.chunks(2) {
             let unit = if little_endian {
@@ -620,16 +1135,16 @@ fn try_utf16(bytes: &[u8], little_endian: bool) -> Option<String> {
             };
             buf.push(unit);
         }
-        if let Ok(s) = String::from_utf16(&buf) {
-            if s.chars().any(|ch| !ch.is_control()) {
-                return Some(s.chars().filter(|ch| !ch.is_control()).collect());
-            }
+        if let Ok(s) = String::from_utf16(&buf)
+            && s.chars().any(|ch| !ch.is_control())
+        {
+            return Some(s.chars().filter(|ch| !ch.is_control()).collect());
         }
     }
     None
 }
 
-fn try_utf16le(bytes: &[u8]) -> Option<String> {
+pub fn try_utf16le(bytes: &[u8]) -> Option<String> {
     try_utf16(bytes, true)
 }
 
@@ -669,3 +1184,124 @@ fn decode_varint_utf16be(bytes: &[u8]) -> String {
     let slice = &bytes[consumed..consumed + val as usize * 2]; // varint gives number of UTF-16 code units
     try_utf16be(slice).unwrap_or_else(|| bytes_to_utf8_lossy(slice))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indexeddb_key_prefix_one_byte_ids() {
+        let (prefix, len) = decode_indexeddb_key_prefix(&[0x00, 0x01, 0x02, 0x03, 0xFF]).unwrap();
+        assert_eq!(
+            prefix,
+            IndexedDbKeyPrefix {
+                database_id: 1,
+                object_store_id: 2,
+                index_id: 3,
+            }
+        );
+        assert_eq!(len, 4);
+    }
+
+    #[test]
+    fn indexeddb_key_prefix_little_endian_ids() {
+        // database id and object store id 2 bytes, index id 2 bytes
+        let bytes = [0x25, 0x34, 0x12, 0x01, 0x00, 0x1E, 0x00];
+        let (prefix, len) = decode_indexeddb_key_prefix(&bytes).unwrap();
+        assert_eq!(prefix.database_id, 0x1234);
+        assert_eq!(prefix.object_store_id, 1);
+        assert_eq!(prefix.index_id, 30);
+        assert_eq!(len, 7);
+    }
+
+    #[test]
+    fn indexeddb_key_prefix_truncated() {
+        assert_eq!(decode_indexeddb_key_prefix(&[]), None);
+        assert_eq!(decode_indexeddb_key_prefix(&[0x25, 0x34, 0x12]), None);
+    }
+
+    fn keys(keys: &[&'static [u8]]) -> Vec<&'static [u8]> {
+        keys.to_vec()
+    }
+
+    #[test]
+    fn guess_from_marker_and_entry_keys() {
+        let guess = guess_storage_kind(keys(&[
+            b"VERSION",
+            b"META:https://example.com",
+            b"_https://example.com\x00\x01theme",
+            b"_https://example.com\x00\x01lang",
+        ]));
+        assert_eq!(guess.kind, StorageKind::LocalStorage);
+        assert_eq!(guess.confidence, 1.0);
+
+        let guess = guess_storage_kind(keys(&[b"next-map-id", b"map-1-a", b"map-1-b"]));
+        assert_eq!(guess.kind, StorageKind::SessionStorage);
+        assert_eq!(guess.confidence, 1.0);
+
+        let guess = guess_storage_kind(keys(&[]));
+        assert_eq!(guess.kind, StorageKind::Generic);
+        assert_eq!(guess.confidence, 0.0);
+    }
+
+    #[test]
+    fn one_marker_among_foreign_keys_stays_weak() {
+        let mut key_set = keys(&[b"META:https://example.com"]);
+        key_set.extend(keys(&[
+            b"alpha", b"beta", b"gamma", b"delta", b"epsilon", b"zeta", b"eta", b"theta", b"iota",
+        ]));
+
+        let guess = guess_storage_kind(key_set.iter().copied());
+        assert_eq!(guess.kind, StorageKind::LocalStorage);
+        assert!(guess.confidence >= MIN_GUESS_CONFIDENCE);
+        assert!(guess.confidence < STRONG_GUESS_CONFIDENCE);
+
+        // enough for a path without hint, not enough to overrule a path kind
+        assert_eq!(
+            detect_storage_kind_with_keys("/tmp/db", key_set.iter().copied()),
+            StorageKind::LocalStorage
+        );
+        assert_eq!(
+            detect_storage_kind_with_keys("Default/IndexedDB/x.leveldb", key_set.iter().copied()),
+            StorageKind::IndexedDb
+        );
+    }
+
+    #[test]
+    fn mixed_keys_pick_the_dominant_kind() {
+        // Session Storage marker and entries outweigh one Local Storage entry
+        let key_set = keys(&[
+            b"namespace-8f3a1c2e_4b5d_4e6f_9a7b_1c2d3e4f5a6b-https://example.com/",
+            b"map-4-theme",
+            b"map-4-lang",
+            b"_https://example.com\x00\x01theme",
+        ]);
+        let guess = guess_storage_kind(key_set.iter().copied());
+        assert_eq!(guess.kind, StorageKind::SessionStorage);
+        assert_eq!(guess.confidence, 0.5 + 0.5 * 0.75);
+
+        // entry keys without marker: share of the keys only
+        let guess = guess_storage_kind(keys(&[b"map-1-a", b"map-1-b", b"other", b"keys"]));
+        assert_eq!(guess.kind, StorageKind::SessionStorage);
+        assert_eq!(guess.confidence, 0.5);
+    }
+
+    #[test]
+    fn strong_guess_overrides_path_kind() {
+        let key_set = keys(&[
+            b"VERSION",
+            b"META:https://example.com",
+            b"_https://example.com\x00\x01theme",
+        ]);
+        // content says Local Storage, path says Session Storage
+        assert_eq!(
+            detect_storage_kind_with_keys("Default/Session Storage", key_set.iter().copied()),
+            StorageKind::LocalStorage
+        );
+        // no content hint keeps the path kind
+        assert_eq!(
+            detect_storage_kind_with_keys("Default/Session Storage", keys(&[b"other"])),
+            StorageKind::SessionStorage
+        );
+    }
+}
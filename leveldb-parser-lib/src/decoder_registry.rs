@@ -0,0 +1,251 @@
+use crate::bitcoin;
+use crate::decoder::{self, StorageKind};
+
+// -----------------------------------------------------------------------------
+pub struct DecodeContext<'a> {
+    pub path: &'a str,
+    pub storage_kind: StorageKind,
+    pub key: &'a [u8],
+    pub value: Option<&'a [u8]>,
+}
+
+pub trait ValueDecoder: Send + Sync {
+    fn name(&self) -> &str;
+
+    // returns (key, value, kind) like decoder::decode_kv
+    fn decode(&self, ctx: &DecodeContext) -> (String, String, String);
+}
+
+pub type SniffFn = Box<dyn Fn(&DecodeContext) -> bool + Send + Sync>;
+
+pub enum Selector {
+    StorageKind(StorageKind),
+    PathContains(String), // case-insensitive
+    KeyPrefix(Vec<u8>),
+    Content(SniffFn),
+}
+
+impl Selector {
+    pub fn matches(&self, ctx: &DecodeContext) -> bool {
+        match self {
+            Selector::StorageKind(kind) => ctx.storage_kind == *kind,
+            Selector::PathContains(pattern) => ctx
+                .path
+                .to_ascii_lowercase()
+                .contains(&pattern.to_ascii_lowercase()),
+            Selector::KeyPrefix(prefix) => ctx.key.starts_with(prefix),
+            Selector::Content(sniff) => sniff(ctx),
+        }
+    }
+}
+
+// built-in decoders of decoder::decode_kv
+pub struct StorageKindDecoder(pub StorageKind);
+
+impl ValueDecoder for StorageKindDecoder {
+    fn name(&self) -> &str {
+        match self.0 {
+            StorageKind::SessionStorage => "session_storage",
+            StorageKind::LocalStorage => "local_storage",
+            StorageKind::IndexedDb => "indexeddb",
+            StorageKind::ExtensionSettings => "extension_settings",
+            StorageKind::SyncData => "sync_data",
+            StorageKind::GcmStore => "gcm_store",
+            StorageKind::ServiceWorker => "service_worker",
+            StorageKind::SiteCharacteristics => "site_characteristics",
+            StorageKind::SharedProtoDb => "shared_proto_db",
+            StorageKind::PlatformNotifications => "platform_notifications",
+            StorageKind::MinecraftBedrock => "minecraft_bedrock",
+            StorageKind::BitcoinChainstate => "bitcoin_chainstate",
+            StorageKind::BitcoinBlockIndex => "bitcoin_block_index",
+            StorageKind::Protobuf => "protobuf",
+            StorageKind::Generic => "generic",
+        }
+    }
+
+    fn decode(&self, ctx: &DecodeContext) -> (String, String, String) {
+        decoder::decode_kv(self.0, ctx.key, ctx.value)
+    }
+}
+
+// -----------------------------------------------------------------------------
+pub struct DecoderRegistry {
+    decoders: Vec<(Selector, Box<dyn ValueDecoder>)>,
+    fallback: Box<dyn ValueDecoder>,
+}
+
+impl DecoderRegistry {
+    // no decoders besides the generic fallback
+    pub fn empty() -> Self {
+        DecoderRegistry {
+            decoders: Vec::new(),
+            fallback: Box::new(StorageKindDecoder(StorageKind::Generic)),
+        }
+    }
+
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::empty();
+        for kind in [
+            StorageKind::SessionStorage,
+            StorageKind::LocalStorage,
+            StorageKind::IndexedDb,
+            StorageKind::ExtensionSettings,
+            StorageKind::SyncData,
+            StorageKind::GcmStore,
+            StorageKind::ServiceWorker,
+            StorageKind::SiteCharacteristics,
+            StorageKind::SharedProtoDb,
+            StorageKind::PlatformNotifications,
+            StorageKind::MinecraftBedrock,
+            StorageKind::BitcoinBlockIndex,
+            StorageKind::Protobuf,
+        ] {
+            registry.register(
+                Selector::StorageKind(kind),
+                Box::new(StorageKindDecoder(kind)),
+            );
+        }
+        // obfuscated values need the key row of the database
+        registry.register(
+            Selector::StorageKind(StorageKind::BitcoinChainstate),
+            Box::new(bitcoin::ChainstateDirDecoder::default()),
+        );
+        registry
+    }
+
+    // decoders registered later take precedence
+    pub fn register(&mut self, selector: Selector, decoder: Box<dyn ValueDecoder>) {
+        self.decoders.push((selector, decoder));
+    }
+
+    pub fn set_fallback(&mut self, decoder: Box<dyn ValueDecoder>) {
+        self.fallback = decoder;
+    }
+
+    pub fn select(&self, ctx: &DecodeContext) -> &dyn ValueDecoder {
+        self.decoders
+            .iter()
+            .rev()
+            .find(|(selector, _)| selector.matches(ctx))
+            .map(|(_, decoder)| decoder.as_ref())
+            .unwrap_or(self.fallback.as_ref())
+    }
+
+    pub fn decode_kv(&self, ctx: &DecodeContext) -> (String, String, String) {
+        self.select(ctx).decode(ctx)
+    }
+}
+
+impl Default for DecoderRegistry {
+    fn default() -> Self {
+        Self::with_builtins()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct NamedDecoder(&'static str);
+
+    impl ValueDecoder for NamedDecoder {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn decode(&self, ctx: &DecodeContext) -> (String, String, String) {
+            (
+                decoder::bytes_to_utf8_lossy(ctx.key),
+                self.0.to_string(),
+                "X".to_string(),
+            )
+        }
+    }
+
+    fn context<'a>(path: &'a str, storage_kind: StorageKind, key: &'a [u8]) -> DecodeContext<'a> {
+        DecodeContext {
+            path,
+            storage_kind,
+            key,
+            value: Some(b"value"),
+        }
+    }
+
+    #[test]
+    fn later_registration_wins() {
+        let mut registry = DecoderRegistry::empty();
+        registry.register(
+            Selector::KeyPrefix(b"a".to_vec()),
+            Box::new(NamedDecoder("first")),
+        );
+        registry.register(
+            Selector::KeyPrefix(b"a".to_vec()),
+            Box::new(NamedDecoder("second")),
+        );
+
+        let ctx = context("db", StorageKind::Generic, b"abc");
+        assert_eq!(registry.select(&ctx).name(), "second");
+
+        // nothing matches => fallback
+        let ctx = context("db", StorageKind::Generic, b"xyz");
+        assert_eq!(registry.select(&ctx).name(), "generic");
+    }
+
+    #[test]
+    fn selector_precedence_by_registration_order() {
+        let mut registry = DecoderRegistry::empty();
+        registry.register(
+            Selector::PathContains("Session Storage".to_string()),
+            Box::new(NamedDecoder("path")),
+        );
+        registry.register(
+            Selector::KeyPrefix(b"map-".to_vec()),
+            Box::new(NamedDecoder("prefix")),
+        );
+        registry.register(
+            Selector::Content(Box::new(|ctx| ctx.key.ends_with(b"-sniffed"))),
+            Box::new(NamedDecoder("content")),
+        );
+
+        let path = "Default/session storage/000003.log"; // case-insensitive
+        let select = |key: &'static [u8]| {
+            let ctx = context(path, StorageKind::Generic, key);
+            registry.select(&ctx).name().to_string()
+        };
+        assert_eq!(select(b"map-1-sniffed"), "content");
+        assert_eq!(select(b"map-1-key"), "prefix");
+        assert_eq!(select(b"next-map-id"), "path");
+
+        let ctx = context(
+            "Default/Local Storage",
+            StorageKind::Generic,
+            b"next-map-id",
+        );
+        assert_eq!(registry.select(&ctx).name(), "generic");
+    }
+
+    #[test]
+    fn custom_decoder_overrides_builtin_for_key_prefix_only() {
+        let mut registry = DecoderRegistry::with_builtins();
+        registry.register(
+            Selector::KeyPrefix(b"META:".to_vec()),
+            Box::new(NamedDecoder("custom_meta")),
+        );
+
+        let ctx = context(
+            "Local Storage",
+            StorageKind::LocalStorage,
+            b"META:https://a",
+        );
+        assert_eq!(registry.select(&ctx).name(), "custom_meta");
+        assert_eq!(registry.decode_kv(&ctx).1, "custom_meta");
+
+        // other keys of the store keep the built-in decoder
+        let ctx = context("Local Storage", StorageKind::LocalStorage, b"VERSION");
+        assert_eq!(registry.select(&ctx).name(), "local_storage");
+        assert_eq!(
+            registry.decode_kv(&ctx),
+            decoder::decode_kv(StorageKind::LocalStorage, b"VERSION", Some(b"value"))
+        );
+    }
+}
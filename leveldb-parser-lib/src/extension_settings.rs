@@ -0,0 +1,165 @@
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::database::DbRecord;
+use crate::decoder;
+
+// Local Extension Settings/<extension id>: chrome.storage.local of one
+// extension, plain string keys with JSON-serialized values
+// -----------------------------------------------------------------------------
+pub struct ExtensionSettings {
+    pub extension_id: Option<String>,
+    pub entries: Vec<ExtensionSetting>,
+}
+
+pub struct ExtensionSetting {
+    pub key: String,
+    pub value: Option<Value>, // None if deleted or not valid JSON
+    pub raw_value: String,
+    pub seq: u64,
+    pub state: u8,
+    pub file_path: String,
+}
+
+// -----------------------------------------------------------------------------
+pub fn build(db_path: &str, records: &[DbRecord]) -> ExtensionSettings {
+    let entries = records
+        .iter()
+        .map(|record| ExtensionSetting {
+            key: decoder::bytes_to_utf8_lossy(&record.key),
+            value: record.value.as_deref().and_then(parse_json),
+            raw_value: record
+                .value
+                .as_deref()
+                .map(decoder::bytes_to_utf8_lossy)
+                .unwrap_or_default(),
+            seq: record.seq,
+            state: record.state,
+            file_path: record.file_path.clone(),
+        })
+        .collect();
+
+    ExtensionSettings {
+        extension_id: extension_id_from_path(db_path),
+        entries,
+    }
+}
+
+pub fn parse_json(bytes: &[u8]) -> Option<Value> {
+    serde_json::from_slice(bytes).ok()
+}
+
+// directory below "Local Extension Settings", ids are 32 chars of 'a'..='p'
+pub fn extension_id_from_path(path: &str) -> Option<String> {
+    let mut components = Path::new(path).components().map(|c| c.as_os_str());
+    components.find(|c| c.eq_ignore_ascii_case("Local Extension Settings"))?;
+    let id = components.next()?.to_str()?;
+
+    let is_extension_id = id.len() == 32 && id.bytes().all(|b| (b'a'..=b'p').contains(&b));
+    is_extension_id.then(|| id.to_string())
+}
+
+// -----------------------------------------------------------------------------
+pub mod export {
+    use super::*;
+
+    pub fn csv_string(settings: &ExtensionSettings) -> String {
+        let mut csv = String::new();
+        // Header
+        csv.push_str("\"Seq\",\"Extension\",\"K\",\"V\",\"St\",\"FP\"\n");
+
+        for entry in &settings.entries {
+            let state_str = match entry.state {
+                0 => "deleted",
+                1 => "live",
+                _ => "unknown",
+            };
+
+            let value_str = match &entry.value {
+                Some(value) => value.to_string(),
+                None => entry.raw_value.clone(),
+            };
+
+            csv.push_str(&format!(
+                "\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\"\n",
+                entry.seq,
+                settings.extension_id.as_deref().unwrap_or(""),
+                entry.key.replace("\"", "\"\""),
+                value_str.replace("\"", "\"\""),
+                state_str,
+                entry.file_path,
+            ));
+        }
+
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "abcdefghijklmnopabcdefghijklmnop";
+
+    fn record(key: &str, seq: u64, value: Option<&str>) -> DbRecord {
+        DbRecord {
+            seq,
+            state: value.is_some() as u8,
+            key: key.as_bytes().to_vec(),
+            value: value.map(|value| value.as_bytes().to_vec()),
+            file_path: "000003.log".to_string(),
+            block_offset: 0,
+            key_offset: 0,
+        }
+    }
+
+    #[test]
+    fn extension_id_below_settings_dir() {
+        let path = format!("Default/Local Extension Settings/{}/000003.log", ID);
+        assert_eq!(extension_id_from_path(&path), Some(ID.to_string()));
+        let path = format!("Default/local extension settings/{}", ID);
+        assert_eq!(extension_id_from_path(&path), Some(ID.to_string()));
+    }
+
+    #[test]
+    fn extension_id_rejects_other_names() {
+        // 'q' and upper case are outside 'a'..='p'
+        let path = "Default/Local Extension Settings/abcdefghijklmnopabcdefghijklmnoq";
+        assert_eq!(extension_id_from_path(path), None);
+        let path = "Default/Local Extension Settings/ABCDEFGHIJKLMNOPABCDEFGHIJKLMNOP";
+        assert_eq!(extension_id_from_path(path), None);
+        // 31 and 33 chars
+        let path = format!("Default/Local Extension Settings/{}", &ID[..31]);
+        assert_eq!(extension_id_from_path(&path), None);
+        let path = format!("Default/Local Extension Settings/{}a", ID);
+        assert_eq!(extension_id_from_path(&path), None);
+        // no settings dir or nothing below it
+        assert_eq!(extension_id_from_path(&format!("Default/{}", ID)), None);
+        assert_eq!(
+            extension_id_from_path("Default/Local Extension Settings"),
+            None
+        );
+    }
+
+    #[test]
+    fn settings_values_parsed_as_json() {
+        let records = [
+            record("theme", 1, Some(r#"{"dark":true}"#)),
+            record("broken", 2, Some("{not json")),
+            record("removed", 3, None),
+        ];
+        let path = format!("Local Extension Settings/{}", ID);
+        let settings = build(&path, &records);
+
+        assert_eq!(settings.extension_id.as_deref(), Some(ID));
+        assert_eq!(
+            settings.entries[0].value,
+            Some(serde_json::json!({ "dark": true }))
+        );
+        assert_eq!(settings.entries[1].value, None);
+        assert_eq!(settings.entries[1].raw_value, "{not json");
+        assert_eq!(settings.entries[2].value, None);
+        assert_eq!(settings.entries[2].state, 0);
+    }
+}
@@ -0,0 +1,355 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::verify;
+
+// files of a database directory by the LevelDB file name patterns
+//   000005.log / 000006.ldb / 000007.sst    numbered log / tables
+//   MANIFEST-000002                         manifest
+//   MANIFEST-000002.bak / MANIFEST-000002~  manifest backup (not written by
+//                                           LevelDB, e.g. by tools or editors)
+//   CURRENT, LOCK, LOG, LOG.old             fixed names
+//   000008.dbtmp                            temp file of a CURRENT update
+// -----------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileKind {
+    Log,
+    Table, // .ldb
+    Sst,   // table of older LevelDB versions
+    Manifest,
+    ManifestBackup,
+    Current,
+    Lock,
+    InfoLog,    // LOG
+    OldInfoLog, // LOG.old
+    Temp,       // .dbtmp
+    Unknown,
+}
+
+pub struct InventoryEntry {
+    pub file_name: String,
+    pub kind: FileKind,
+    pub file_no: Option<u64>,
+    pub file_size: u64,
+}
+
+pub struct Inventory {
+    pub dir_path: String,
+    pub entries: Vec<InventoryEntry>, // ordered by kind and file number
+    pub current: Option<String>,      // manifest name of CURRENT
+    pub anomalies: Vec<String>,
+}
+
+impl FileKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FileKind::Log => "log",
+            FileKind::Table => "ldb",
+            FileKind::Sst => "sst",
+            FileKind::Manifest => "manifest",
+            FileKind::ManifestBackup => "manifest backup",
+            FileKind::Current => "current",
+            FileKind::Lock => "lock",
+            FileKind::InfoLog => "info log",
+            FileKind::OldInfoLog => "old info log",
+            FileKind::Temp => "temp",
+            FileKind::Unknown => "unknown",
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// kind and file number of a file name
+pub fn classify_name(file_name: &str) -> (FileKind, Option<u64>) {
+    match file_name {
+        "CURRENT" => return (FileKind::Current, None),
+        "LOCK" => return (FileKind::Lock, None),
+        "LOG" => return (FileKind::InfoLog, None),
+        "LOG.old" => return (FileKind::OldInfoLog, None),
+        _ => {}
+    }
+
+    if let Some(number) = file_name.strip_prefix("MANIFEST-") {
+        // digits, then a backup suffix starting with '.' or '~'
+        let digits_len = number.bytes().take_while(u8::is_ascii_digit).count();
+        let (digits, suffix) = number.split_at(digits_len);
+        return match parse_number(digits) {
+            Some(file_no) if suffix.is_empty() => (FileKind::Manifest, Some(file_no)),
+            Some(file_no) if suffix.starts_with(['.', '~']) => {
+                (FileKind::ManifestBackup, Some(file_no))
+            }
+            _ => (FileKind::Unknown, None),
+        };
+    }
+
+    let kind = match Path::new(file_name)
+        .extension()
+        .and_then(|ext| ext.to_str())
+    {
+        Some("log") => FileKind::Log,
+        Some("ldb") => FileKind::Table,
+        Some("sst") => FileKind::Sst,
+        Some("dbtmp") => FileKind::Temp,
+        _ => return (FileKind::Unknown, None),
+    };
+    let stem = Path::new(file_name)
+        .file_stem()
+        .and_then(|stem| stem.to_str());
+    match stem.and_then(parse_number) {
+        Some(file_no) => (kind, Some(file_no)),
+        None => (FileKind::Unknown, None),
+    }
+}
+
+pub fn inventory_dir(dir_path: &str) -> io::Result<Inventory> {
+    let dir = Path::new(dir_path);
+    let mut entries = Vec::new();
+
+    for entry in fs::read_dir(dir)?.flatten() {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if !metadata.is_file() {
+            continue;
+        }
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let (kind, file_no) = classify_name(&file_name);
+        entries.push(InventoryEntry {
+            file_name,
+            kind,
+            file_no,
+            file_size: metadata.len(),
+        });
+    }
+    entries.sort_by(|a, b| {
+        kind_order(a.kind)
+            .cmp(&kind_order(b.kind))
+            .then_with(|| a.file_no.cmp(&b.file_no))
+            .then_with(|| a.file_name.cmp(&b.file_name))
+    });
+
+    let current = verify::read_current(dir);
+    let anomalies = find_anomalies(&entries, &current);
+
+    Ok(Inventory {
+        dir_path: dir_path.to_string(),
+        entries,
+        current: current.ok(),
+        anomalies,
+    })
+}
+
+// current: manifest name read from CURRENT
+fn find_anomalies(entries: &[InventoryEntry], current: &io::Result<String>) -> Vec<String> {
+    let mut anomalies = Vec::new();
+    let has = |kind: FileKind| entries.iter().any(|entry| entry.kind == kind);
+
+    // CURRENT
+    match current {
+        _ if !has(FileKind::Current) => anomalies.push("No CURRENT file".to_string()),
+        Err(e) => anomalies.push(format!("CURRENT: {}", e)),
+        Ok(name) if !entries.iter().any(|entry| &entry.file_name == name) => {
+            anomalies.push(format!("CURRENT points to missing {}", name));
+        }
+        Ok(_) => {}
+    }
+    let current = current.as_deref().ok();
+
+    // manifests
+    let manifests: Vec<&str> = entries
+        .iter()
+        .filter(|entry| entry.kind == FileKind::Manifest)
+        .map(|entry| entry.file_name.as_str())
+        .collect();
+    if manifests.is_empty() {
+        anomalies.push("No MANIFEST file".to_string());
+    } else if manifests.len() > 1 {
+        anomalies.push(format!(
+            "{} manifests: {} (CURRENT: {})",
+            manifests.len(),
+            manifests.join(", "),
+            current.unwrap_or("-")
+        ));
+    }
+
+    // numbers are unique over logs, tables, manifests and temp files, a
+    // backup shares the number of its manifest
+    let mut numbers: BTreeMap<u64, Vec<&str>> = BTreeMap::new();
+    for entry in entries {
+        if let Some(file_no) = entry.file_no
+            && entry.kind != FileKind::ManifestBackup
+        {
+            numbers.entry(file_no).or_default().push(&entry.file_name);
+        }
+    }
+    for (file_no, names) in numbers.iter().filter(|(_, names)| names.len() > 1) {
+        anomalies.push(format!(
+            "File number {} used by {}",
+            file_no,
+            names.join(", ")
+        ));
+    }
+
+    if !has(FileKind::Lock) {
+        anomalies.push("No LOCK file".to_string());
+    }
+    if has(FileKind::OldInfoLog) && !has(FileKind::InfoLog) {
+        anomalies.push("LOG.old without LOG".to_string());
+    }
+
+    for entry in entries {
+        match entry.kind {
+            FileKind::Temp => anomalies.push(format!(
+                "Temp file {} (interrupted CURRENT update)",
+                entry.file_name
+            )),
+            FileKind::ManifestBackup => {
+                anomalies.push(format!("Manifest backup {}", entry.file_name))
+            }
+            FileKind::Unknown => anomalies.push(format!("Unknown file {}", entry.file_name)),
+            _ => {}
+        }
+    }
+
+    anomalies
+}
+
+// "000002" => 2, digits only
+fn parse_number(number: &str) -> Option<u64> {
+    if number.is_empty() || !number.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    number.parse().ok()
+}
+
+fn kind_order(kind: FileKind) -> u8 {
+    match kind {
+        FileKind::Current => 0,
+        FileKind::Lock => 1,
+        FileKind::InfoLog => 2,
+        FileKind::OldInfoLog => 3,
+        FileKind::Manifest => 4,
+        FileKind::ManifestBackup => 5,
+        FileKind::Log => 6,
+        FileKind::Table | FileKind::Sst => 7,
+        FileKind::Temp => 8,
+        FileKind::Unknown => 9,
+    }
+}
+
+// -----------------------------------------------------------------------------
+pub mod display {
+    use super::*;
+
+    pub fn print_inventory(inventory: &Inventory) -> io::Result<()> {
+        writeln!(io::stdout(), "Database: {}", inventory.dir_path)?;
+        writeln!(
+            io::stdout(),
+            "CURRENT: {}",
+            inventory.current.as_deref().unwrap_or("-")
+        )?;
+        for entry in &inventory.entries {
+            let file_no = entry
+                .file_no
+                .map(|file_no| format!(" #{}", file_no))
+                .unwrap_or_default();
+            writeln!(
+                io::stdout(),
+                "{:<15} {}{}, {} bytes",
+                entry.kind.name(),
+                entry.file_name,
+                file_no,
+                entry.file_size
+            )?;
+        }
+        if inventory.anomalies.is_empty() {
+            writeln!(io::stdout(), "No anomalies")?;
+        } else {
+            writeln!(io::stdout(), "Anomalies:")?;
+            for anomaly in &inventory.anomalies {
+                writeln!(io::stdout(), "  {}", anomaly)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+pub mod export {
+    use super::*;
+
+    pub fn csv_string(inventory: &Inventory) -> String {
+        let mut csv = String::new();
+        // Header
+        csv.push_str("\"Kind\",\"File\",\"No\",\"Size\",\"DB\"\n");
+
+        for entry in &inventory.entries {
+            csv.push_str(&format!(
+                "\"{}\",\"{}\",\"{}\",\"{}\",\"{}\"\n",
+                entry.kind.name(),
+                entry.file_name.replace("\"", "\"\""),
+                entry
+                    .file_no
+                    .map(|file_no| file_no.to_string())
+                    .unwrap_or_default(),
+                entry.file_size,
+                inventory.dir_path.replace("\"", "\"\""),
+            ));
+        }
+
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify_fixed_names() {
+        assert_eq!(classify_name("CURRENT"), (FileKind::Current, None));
+        assert_eq!(classify_name("LOCK"), (FileKind::Lock, None));
+        assert_eq!(classify_name("LOG"), (FileKind::InfoLog, None));
+        assert_eq!(classify_name("LOG.old"), (FileKind::OldInfoLog, None));
+    }
+
+    #[test]
+    fn classify_numbered_files() {
+        assert_eq!(classify_name("000005.log"), (FileKind::Log, Some(5)));
+        assert_eq!(classify_name("000006.ldb"), (FileKind::Table, Some(6)));
+        assert_eq!(classify_name("000007.sst"), (FileKind::Sst, Some(7)));
+        assert_eq!(classify_name("000008.dbtmp"), (FileKind::Temp, Some(8)));
+        assert_eq!(
+            classify_name("MANIFEST-000002"),
+            (FileKind::Manifest, Some(2))
+        );
+    }
+
+    #[test]
+    fn classify_manifest_backups() {
+        assert_eq!(
+            classify_name("MANIFEST-000002.bak"),
+            (FileKind::ManifestBackup, Some(2))
+        );
+        assert_eq!(
+            classify_name("MANIFEST-000002~"),
+            (FileKind::ManifestBackup, Some(2))
+        );
+    }
+
+    #[test]
+    fn classify_unknown_names() {
+        for name in [
+            "MANIFEST-",
+            "MANIFEST-00x",
+            "MANIFEST-000002-copy",
+            "abc.log",
+            ".ldb",
+            "000005.txt",
+            "current",
+        ] {
+            assert_eq!(classify_name(name), (FileKind::Unknown, None), "{}", name);
+        }
+    }
+}
@@ -0,0 +1,552 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+use crate::database::{self, DbRecord};
+use crate::decoder::StorageKind;
+use crate::decoder_registry::{DecodeContext, DecoderRegistry};
+use crate::inventory::{self, FileKind};
+use crate::search::{self, Field, SearchMode, SearchQuery};
+
+// indicator file, one indicator per line ('#' starts a comment line):
+//   <text>            literal, in all encodings of search (bare hex
+//                     digits as well, hashes need their prefix)
+//   literal:<text>    literal, also for text starting with a prefix
+//   icase:<text>      case-insensitive literal
+//   regex:<pattern>   regex
+//   md5:<hex> / sha1:<hex> / sha256:<hex>
+//                     hash of a raw or decoded value
+// -----------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum HashAlgorithm {
+    Md5,
+    Sha1,
+    Sha256,
+}
+
+pub enum IndicatorKind {
+    Text(SearchQuery),
+    ValueHash(HashAlgorithm, Vec<u8>),
+}
+
+pub struct Indicator {
+    pub line_no: usize,
+    pub source: String, // line of the indicator file
+    pub kind: IndicatorKind,
+}
+
+pub struct IocHit {
+    pub line_no: usize,
+    pub indicator: String,
+    pub db_path: String,
+    pub location: String, // e.g. "value UTF-16LE @13", "value sha256 (decoded)"
+    pub matched: String,
+    pub file_path: String,
+    pub seq: u64,
+    pub state: u8,
+    pub block_offset: u64,
+    pub key_offset: u64,
+    pub key: String, // decoded
+    pub value: String,
+    pub kind: String,
+}
+
+pub struct SweepReport {
+    pub databases: Vec<String>,
+    pub errors: Vec<String>, // unreadable directories / files
+    pub hits: Vec<IocHit>,
+}
+
+impl HashAlgorithm {
+    pub fn name(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Md5 => "md5",
+            HashAlgorithm::Sha1 => "sha1",
+            HashAlgorithm::Sha256 => "sha256",
+        }
+    }
+
+    fn from_digest_len(len: usize) -> Option<Self> {
+        match len {
+            16 => Some(HashAlgorithm::Md5),
+            20 => Some(HashAlgorithm::Sha1),
+            32 => Some(HashAlgorithm::Sha256),
+            _ => None,
+        }
+    }
+
+    pub fn digest(&self, data: &[u8]) -> Vec<u8> {
+        match self {
+            HashAlgorithm::Md5 => Md5::digest(data).to_vec(),
+            HashAlgorithm::Sha1 => Sha1::digest(data).to_vec(),
+            HashAlgorithm::Sha256 => Sha256::digest(data).to_vec(),
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+pub fn load_indicators(file_path: &str) -> io::Result<Vec<Indicator>> {
+    let text = fs::read_to_string(file_path)?;
+    parse_indicators(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+pub fn parse_indicators(text: &str) -> Result<Vec<Indicator>, String> {
+    let mut indicators = Vec::new();
+
+    for (idx, line) in text.lines().enumerate() {
+        let line_no = idx + 1;
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let kind = parse_indicator(line).map_err(|e| format!("Line {}: {}", line_no, e))?;
+        indicators.push(Indicator {
+            line_no,
+            source: line.to_string(),
+            kind,
+        });
+    }
+
+    Ok(indicators)
+}
+
+fn parse_indicator(line: &str) -> Result<IndicatorKind, String> {
+    let hash = |algorithm: HashAlgorithm, hex: &str| -> Result<IndicatorKind, String> {
+        let digest = parse_hex(hex.trim()).ok_or_else(|| format!("Invalid hex '{}'", hex))?;
+        if HashAlgorithm::from_digest_len(digest.len()) != Some(algorithm) {
+            return Err(format!("Invalid {} length", algorithm.name()));
+        }
+        Ok(IndicatorKind::ValueHash(algorithm, digest))
+    };
+
+    if let Some(text) = line.strip_prefix("literal:") {
+        Ok(IndicatorKind::Text(SearchQuery::new(
+            text,
+            SearchMode::Literal,
+        )?))
+    } else if let Some(text) = line.strip_prefix("icase:") {
+        Ok(IndicatorKind::Text(SearchQuery::new(
+            text,
+            SearchMode::CaseInsensitive,
+        )?))
+    } else if let Some(pattern) = line.strip_prefix("regex:") {
+        Ok(IndicatorKind::Text(SearchQuery::new(
+            pattern,
+            SearchMode::Regex,
+        )?))
+    } else if let Some(hex) = line.strip_prefix("md5:") {
+        hash(HashAlgorithm::Md5, hex)
+    } else if let Some(hex) = line.strip_prefix("sha1:") {
+        hash(HashAlgorithm::Sha1, hex)
+    } else if let Some(hex) = line.strip_prefix("sha256:") {
+        hash(HashAlgorithm::Sha256, hex)
+    } else {
+        Ok(IndicatorKind::Text(SearchQuery::new(
+            line,
+            SearchMode::Literal,
+        )?))
+    }
+}
+
+// -----------------------------------------------------------------------------
+// directories below root (root included) with LevelDB files, symlinks are
+// not followed
+pub fn find_databases(root: &Path) -> (Vec<PathBuf>, Vec<String>) {
+    let mut databases = Vec::new();
+    let mut errors = Vec::new();
+    collect_databases(root, &mut databases, &mut errors);
+    databases.sort();
+    (databases, errors)
+}
+
+pub fn sweep(root: &Path, indicators: &[Indicator]) -> SweepReport {
+    let registry = DecoderRegistry::default();
+    let (db_paths, mut errors) = find_databases(root);
+    let mut databases = Vec::new();
+    let mut hits = Vec::new();
+
+    for db_path in db_paths {
+        let db_path_str = db_path.to_string_lossy().to_string();
+        let db = match database::parse_dir(&db_path_str) {
+            Ok(db) => db,
+            Err(e) => {
+                errors.push(format!("{}: {}", db_path_str, e));
+                continue;
+            }
+        };
+        errors.extend(db.parse_errors.iter().cloned());
+
+        let records = database::collect_records(&db);
+        hits.extend(sweep_records(
+            &db_path_str,
+            &records,
+            db.storage_kind,
+            &registry,
+            indicators,
+        ));
+        databases.push(db_path_str);
+    }
+
+    SweepReport {
+        databases,
+        errors,
+        hits,
+    }
+}
+
+// every record is decoded once and hashed once per algorithm, the hits are
+// ordered by indicator
+pub fn sweep_records(
+    db_path: &str,
+    records: &[DbRecord],
+    storage_kind: StorageKind,
+    registry: &DecoderRegistry,
+    indicators: &[Indicator],
+) -> Vec<IocHit> {
+    let mut hits = Vec::new();
+
+    for record in records {
+        let decoded = registry.decode_kv(&DecodeContext {
+            path: &record.file_path,
+            storage_kind,
+            key: &record.key,
+            value: record.value.as_deref(),
+        });
+        let (key_str, value_str, kind_str) = &decoded;
+        // (raw, decoded) value digests per algorithm, computed on first use
+        let mut digests: HashMap<HashAlgorithm, (Vec<u8>, Vec<u8>)> = HashMap::new();
+
+        for indicator in indicators {
+            match &indicator.kind {
+                IndicatorKind::Text(query) => {
+                    for hit in search::search_record(record, &decoded, query) {
+                        let field = match hit.field {
+                            Field::Key => "key",
+                            Field::Value => "value",
+                        };
+                        let location = match hit.offset {
+                            Some(offset) => {
+                                format!("{} {} @{}", field, hit.encoding.name(), offset)
+                            }
+                            None => format!("{} {}", field, hit.encoding.name()),
+                        };
+                        hits.push(IocHit {
+                            line_no: indicator.line_no,
+                            indicator: indicator.source.clone(),
+                            db_path: db_path.to_string(),
+                            location,
+                            matched: hit.matched,
+                            file_path: hit.file_path,
+                            seq: hit.seq,
+                            state: hit.state,
+                            block_offset: hit.block_offset,
+                            key_offset: hit.key_offset,
+                            key: hit.key,
+                            value: hit.value,
+                            kind: hit.kind,
+                        });
+                    }
+                }
+                IndicatorKind::ValueHash(algorithm, digest) => {
+                    let Some(value) = &record.value else {
+                        continue;
+                    };
+
+                    let (raw_digest, decoded_digest) =
+                        digests.entry(*algorithm).or_insert_with(|| {
+                            (
+                                algorithm.digest(value),
+                                algorithm.digest(value_str.as_bytes()),
+                            )
+                        });
+                    let source = if raw_digest == digest {
+                        "raw"
+                    } else if decoded_digest == digest {
+                        "decoded"
+                    } else {
+                        continue;
+                    };
+                    hits.push(IocHit {
+                        line_no: indicator.line_no,
+                        indicator: indicator.source.clone(),
+                        db_path: db_path.to_string(),
+                        location: format!("value {} ({})", algorithm.name(), source),
+                        matched: bytes_to_hex_digest(digest),
+                        file_path: record.file_path.clone(),
+                        seq: record.seq,
+                        state: record.state,
+                        block_offset: record.block_offset,
+                        key_offset: record.key_offset,
+                        key: key_str.clone(),
+                        value: value_str.clone(),
+                        kind: kind_str.clone(),
+                    });
+                }
+            }
+        }
+    }
+
+    // stable, records stay in order per indicator
+    hits.sort_by_key(|hit| hit.line_no);
+    hits
+}
+
+fn collect_databases(dir: &Path, databases: &mut Vec<PathBuf>, errors: &mut Vec<String>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            errors.push(format!("{}: {}", dir.display(), e));
+            return;
+        }
+    };
+
+    let mut is_database = false;
+    for entry in entries.flatten() {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let name = entry.file_name().to_string_lossy().to_string();
+        if file_type.is_dir() {
+            collect_databases(&entry.path(), databases, errors);
+        } else if file_type.is_file()
+            && matches!(
+                inventory::classify_name(&name).0,
+                FileKind::Current
+                    | FileKind::Manifest
+                    | FileKind::Table
+                    | FileKind::Sst
+                    | FileKind::Log // directories with only a write-ahead log
+            )
+        {
+            is_database = true;
+        }
+    }
+
+    if is_database {
+        databases.push(dir.to_path_buf());
+    }
+}
+
+fn parse_hex(hex: &str) -> Option<Vec<u8>> {
+    // from_str_radix alone would accept a sign ("+1")
+    let is_hex = hex.chars().all(|c| c.is_ascii_hexdigit());
+    if hex.is_empty() || !is_hex || !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+fn bytes_to_hex_digest(digest: &[u8]) -> String {
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// -----------------------------------------------------------------------------
+pub mod export {
+    use super::*;
+
+    pub fn csv_string(report: &SweepReport) -> String {
+        let mut csv = String::new();
+        // Header
+        csv.push_str(
+            "\"Line\",\"Indicator\",\"Location\",\"Match\",\"Seq\",\"St\",\"K\",\"V\",\"Kind\",\"BO\",\"KO\",\"FP\",\"DB\"\n",
+        );
+
+        for hit in &report.hits {
+            let state_str = match hit.state {
+                0 => "deleted",
+                1 => "live",
+                _ => "unknown",
+            };
+
+            csv.push_str(&format!(
+                "\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\"\n",
+                hit.line_no,
+                hit.indicator.replace("\"", "\"\""),
+                hit.location,
+                hit.matched.replace("\"", "\"\""),
+                hit.seq,
+                state_str,
+                hit.key.replace("\"", "\"\""),
+                hit.value.replace("\"", "\"\""),
+                hit.kind,
+                hit.block_offset,
+                hit.key_offset,
+                hit.file_path.replace("\"", "\"\""),
+                hit.db_path.replace("\"", "\"\""),
+            ));
+        }
+
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(key: &[u8], value: Option<&[u8]>) -> DbRecord {
+        DbRecord {
+            seq: 3,
+            state: value.is_some() as u8,
+            key: key.to_vec(),
+            value: value.map(|value| value.to_vec()),
+            file_path: "000003.log".to_string(),
+            block_offset: 0,
+            key_offset: 0,
+        }
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes_to_hex_digest(bytes)
+    }
+
+    fn text_mode(kind: &IndicatorKind) -> Option<(SearchMode, &str)> {
+        match kind {
+            IndicatorKind::Text(query) => Some((query.mode, query.pattern.as_str())),
+            IndicatorKind::ValueHash(..) => None,
+        }
+    }
+
+    #[test]
+    fn indicator_prefixes() {
+        let md5 = hex(&HashAlgorithm::Md5.digest(b"a"));
+        let sha1 = hex(&HashAlgorithm::Sha1.digest(b"a"));
+        let sha256 = hex(&HashAlgorithm::Sha256.digest(b"a"));
+        let text = format!(
+            "# comment\n\nevil.example\r\nliteral:md5:not a hash\nicase:Token\nregex:id=[0-9]+\n  \nmd5:{}\nsha1:{}\nsha256:{}\n",
+            md5,
+            sha1,
+            sha256.to_uppercase()
+        );
+
+        let indicators = parse_indicators(&text).unwrap();
+        let line_nos: Vec<usize> = indicators.iter().map(|i| i.line_no).collect();
+        assert_eq!(line_nos, vec![3, 4, 5, 6, 8, 9, 10]);
+        assert_eq!(indicators[0].source, "evil.example");
+
+        assert_eq!(
+            text_mode(&indicators[0].kind),
+            Some((SearchMode::Literal, "evil.example"))
+        );
+        assert_eq!(
+            text_mode(&indicators[1].kind),
+            Some((SearchMode::Literal, "md5:not a hash"))
+        );
+        assert_eq!(
+            text_mode(&indicators[2].kind),
+            Some((SearchMode::CaseInsensitive, "Token"))
+        );
+        assert_eq!(
+            text_mode(&indicators[3].kind),
+            Some((SearchMode::Regex, "id=[0-9]+"))
+        );
+
+        let hashes: Vec<(HashAlgorithm, String)> = indicators[4..]
+            .iter()
+            .filter_map(|indicator| match &indicator.kind {
+                IndicatorKind::ValueHash(algorithm, digest) => Some((*algorithm, hex(digest))),
+                IndicatorKind::Text(_) => None,
+            })
+            .collect();
+        assert_eq!(
+            hashes,
+            vec![
+                (HashAlgorithm::Md5, md5),
+                (HashAlgorithm::Sha1, sha1),
+                (HashAlgorithm::Sha256, sha256),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_indicators() {
+        let error = |text: &str| parse_indicators(text).err().unwrap();
+
+        assert_eq!(error("# header\nmd5:zz"), "Line 2: Invalid hex 'zz'");
+        assert_eq!(error("md5:abc"), "Line 1: Invalid hex 'abc'");
+        assert_eq!(
+            error("md5:+0000000000000000000000000000000"),
+            "Line 1: Invalid hex '+0000000000000000000000000000000'"
+        );
+        assert_eq!(error("sha1:"), "Line 1: Invalid hex ''");
+        // digest of another algorithm
+        let md5 = hex(&HashAlgorithm::Md5.digest(b"a"));
+        assert_eq!(
+            error(&format!("sha256:{}", md5)),
+            "Line 1: Invalid sha256 length"
+        );
+        assert!(error("regex:(").starts_with("Line 1: Invalid regex '('"));
+        assert_eq!(error("icase:"), "Line 1: Empty search pattern");
+    }
+
+    #[test]
+    fn hash_of_raw_or_decoded_value() {
+        // Local Storage value: flag byte + Latin-1 text, decoded "dark"
+        let raw_value = b"\x01dark";
+        let text = format!(
+            "sha256:{}\nmd5:{}\nsha1:{}\n",
+            hex(&HashAlgorithm::Sha256.digest(raw_value)),
+            hex(&HashAlgorithm::Md5.digest(b"dark")),
+            hex(&HashAlgorithm::Sha1.digest(b"other")),
+        );
+        let indicators = parse_indicators(&text).unwrap();
+        let records = [
+            record(b"_https://a\x00\x01theme", Some(raw_value)),
+            record(b"_https://a\x00\x01gone", None),
+        ];
+
+        let hits = sweep_records(
+            "db",
+            &records,
+            StorageKind::LocalStorage,
+            &DecoderRegistry::default(),
+            &indicators,
+        );
+        let found: Vec<(usize, &str, &str)> = hits
+            .iter()
+            .map(|hit| (hit.line_no, hit.location.as_str(), hit.value.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (1, "value sha256 (raw)", "dark"),
+                (2, "value md5 (decoded)", "dark"),
+            ]
+        );
+        assert_eq!(
+            hits[0].matched,
+            hex(&HashAlgorithm::Sha256.digest(raw_value))
+        );
+    }
+
+    #[test]
+    fn text_hits_by_indicator() {
+        let indicators = parse_indicators("icase:DARK\nliteral:theme\n").unwrap();
+        let records = [record(b"_https://a\x00\x01theme", Some(b"\x01dark"))];
+
+        let hits = sweep_records(
+            "db",
+            &records,
+            StorageKind::LocalStorage,
+            &DecoderRegistry::default(),
+            &indicators,
+        );
+        let found: Vec<(usize, &str, &str)> = hits
+            .iter()
+            .map(|hit| (hit.line_no, hit.location.as_str(), hit.matched.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![(1, "value UTF-8 @1", "dark"), (2, "key UTF-8 @12", "theme"),]
+        );
+    }
+}
@@ -0,0 +1,163 @@
+use crate::database::{self, Database, DbRecord};
+use crate::decoder::StorageKind;
+use crate::decoder_registry::{DecodeContext, DecoderRegistry};
+
+// every put and delete of one user key across the .log batches and .ldb
+// tables of a database, ordered by sequence number
+// -----------------------------------------------------------------------------
+pub struct TimelineEntry {
+    pub seq: u64,
+    pub state: u8,
+    pub key: String, // decoded
+    pub value: String,
+    pub kind: String,
+    pub file_path: String,
+    pub block_offset: u64, // batch offset in .log files
+    pub key_offset: u64,
+}
+
+// -----------------------------------------------------------------------------
+pub fn key_timeline(db: &Database, user_key: &[u8]) -> Vec<TimelineEntry> {
+    timeline_from_records(
+        &database::collect_records(db),
+        db.storage_kind,
+        &DecoderRegistry::default(),
+        user_key,
+    )
+}
+
+pub fn timeline_from_records(
+    records: &[DbRecord],
+    storage_kind: StorageKind,
+    registry: &DecoderRegistry,
+    user_key: &[u8],
+) -> Vec<TimelineEntry> {
+    let mut entries: Vec<TimelineEntry> = records
+        .iter()
+        .filter(|record| record.key == user_key)
+        .map(|record| {
+            let (key, value, kind) = registry.decode_kv(&DecodeContext {
+                path: &record.file_path,
+                storage_kind,
+                key: &record.key,
+                value: record.value.as_deref(),
+            });
+            TimelineEntry {
+                seq: record.seq,
+                state: record.state,
+                key,
+                value,
+                kind,
+                file_path: record.file_path.clone(),
+                block_offset: record.block_offset,
+                key_offset: record.key_offset,
+            }
+        })
+        .collect();
+
+    // a record may be in a .log file and in the table it was compacted into
+    entries.sort_by(|a, b| {
+        a.seq
+            .cmp(&b.seq)
+            .then_with(|| a.file_path.cmp(&b.file_path))
+    });
+    entries
+}
+
+// -----------------------------------------------------------------------------
+pub mod export {
+    use super::*;
+
+    pub fn csv_string(entries: &[TimelineEntry]) -> String {
+        let mut csv = String::new();
+        // Header
+        csv.push_str("\"Seq\",\"St\",\"K\",\"V\",\"Kind\",\"BO\",\"KO\",\"FP\"\n");
+
+        for entry in entries {
+            let state_str = match entry.state {
+                0 => "deleted",
+                1 => "live",
+                _ => "unknown",
+            };
+
+            csv.push_str(&format!(
+                "\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\"\n",
+                entry.seq,
+                state_str,
+                entry.key.replace("\"", "\"\""),
+                entry.value.replace("\"", "\"\""),
+                entry.kind,
+                entry.block_offset,
+                entry.key_offset,
+                entry.file_path.replace("\"", "\"\""),
+            ));
+        }
+
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(key: &str, seq: u64, value: Option<&str>, file_path: &str) -> DbRecord {
+        DbRecord {
+            seq,
+            state: value.is_some() as u8,
+            key: key.as_bytes().to_vec(),
+            value: value.map(|value| value.as_bytes().to_vec()),
+            file_path: file_path.to_string(),
+            block_offset: 0,
+            key_offset: 0,
+        }
+    }
+
+    #[test]
+    fn timeline_of_one_key_by_seq() {
+        let records = [
+            record("key", 7, None, "000009.log"),
+            record("other", 5, Some("x"), "000009.log"),
+            record("key", 3, Some("b"), "000009.log"),
+            record("key", 3, Some("b"), "000008.ldb"),
+            record("key", 1, Some("a"), "000008.ldb"),
+            record("key2", 2, Some("y"), "000008.ldb"),
+        ];
+
+        let entries = timeline_from_records(
+            &records,
+            StorageKind::Generic,
+            &DecoderRegistry::default(),
+            b"key",
+        );
+
+        let timeline: Vec<(u64, u8, &str)> = entries
+            .iter()
+            .map(|entry| (entry.seq, entry.state, entry.file_path.as_str()))
+            .collect();
+        assert_eq!(
+            timeline,
+            vec![
+                (1, 1, "000008.ldb"),
+                (3, 1, "000008.ldb"),
+                (3, 1, "000009.log"),
+                (7, 0, "000009.log"),
+            ]
+        );
+        assert!(entries[0].value.contains('a'));
+    }
+
+    #[test]
+    fn timeline_of_unknown_key_is_empty() {
+        let records = [record("key", 1, Some("a"), "000008.ldb")];
+        assert!(
+            timeline_from_records(
+                &records,
+                StorageKind::Generic,
+                &DecoderRegistry::default(),
+                b"missing",
+            )
+            .is_empty()
+        );
+    }
+}
@@ -1,11 +1,17 @@
+use std::cmp::Ordering;
 use std::fs::File;
 use std::io::{self, BufReader, Cursor, Read, Seek, Write};
 
 use byteorder::{LittleEndian, ReadBytesExt};
 
+use crate::codec::CodecRegistry;
 use crate::decoder;
+use crate::record_filter::RecordFilter;
 use crate::utils;
 
+// largest plausible log2 of the data range covered by one filter
+const MAX_FILTER_BASE_LOG: u8 = 32;
+
 // -----------------------------------------------------------------------------
 pub struct LdbFile {
     pub footer: Footer,
@@ -53,6 +59,8 @@ pub struct DataBlock {
     pub raw_block: RawBlock,
     pub records: Vec<DataRecord>,
     pub block_handle: BlockHandle,
+    pub restart_points: Vec<u32>, // entry offsets with a complete key
+    pub issues: Vec<String>,      // restart array / key order violations
 }
 
 pub struct DataRecord {
@@ -66,11 +74,14 @@ pub struct DataRecord {
 pub struct RawBlock {
     pub data: Vec<u8>,
     pub compression_type: u8,
+    pub codec_name: String,
     pub crc: u32,
     pub crc_valid: bool,
+    pub decompress_error: Option<String>, // data holds the raw bytes then
 }
 
 pub struct KeyValPair {
+    pub entry_offset: u64,
     pub shared_len: usize,
     pub inline_len: usize,
     pub value_len: usize,
@@ -84,26 +95,107 @@ pub struct BloomFilter {
     pub filter_data: Vec<u8>,
     pub array_offset: u32,
     pub base_log: u8,
+    pub filter_offsets: Vec<u32>, // start of each filter in filter_data
+}
+
+// stored key that its data block filter does not contain
+pub struct BloomMismatch {
+    pub block_offset: u64,
+    pub filter_index: usize,
+    pub seq: u64,
+    pub key: Vec<u8>,
+}
+
+impl BloomFilter {
+    // index of the filter covering the data block at block_offset (one per
+    // 2^base_log bytes)
+    pub fn filter_index(&self, block_offset: u64) -> Option<usize> {
+        Some(block_offset.checked_shr(self.base_log as u32)? as usize)
+    }
+
+    pub fn filter_for_block(&self, block_offset: u64) -> Option<&[u8]> {
+        let index = self.filter_index(block_offset)?;
+        let start = *self.filter_offsets.get(index)? as usize;
+        let end = self
+            .filter_offsets
+            .get(index + 1)
+            .map_or(self.array_offset as usize, |&end| end as usize);
+        self.filter_data.get(start..end)
+    }
+
+    // false only if the user key is definitely not in the data block
+    pub fn may_contain(&self, block_offset: u64, key: &[u8]) -> bool {
+        match self.filter_for_block(block_offset) {
+            Some(filter) => bloom_filter_may_match(filter, key),
+            None => true, // blocks without filter are not excluded
+        }
+    }
 }
 
 // -----------------------------------------------------------------------------
 pub fn parse_file(file_path: &str) -> io::Result<LdbFile> {
+    parse_file_with_codecs(file_path, &CodecRegistry::for_path(file_path))
+}
+
+pub fn parse_file_with_codecs(file_path: &str, codecs: &CodecRegistry) -> io::Result<LdbFile> {
     let file = File::open(file_path)?;
     let mut reader = BufReader::new(file);
 
-    let storage_kind = decoder::detect_storage_kind(file_path);
-
     // Footer
     let footer = read_footer(&mut reader)?;
 
     // Meta Index Block
-    let meta_index_raw = read_raw_block(
-        &mut reader,
-        footer.meta_index_handle.offset,
-        footer.meta_index_handle.size,
-    )?;
-    let meta_index_kvs = read_block_data_kvs(&meta_index_raw.data)?;
-    let meta_index_records = meta_index_kvs
+    let meta_index_block = read_index_block(&mut reader, codecs, footer.meta_index_handle)?;
+
+    // Meta Blocks
+    let meta_blocks = read_meta_blocks(&mut reader, codecs, &meta_index_block)?;
+
+    // Index Block
+    let index_block = read_index_block(&mut reader, codecs, footer.index_handle)?;
+
+    // Data Blocks
+    let mut data_blocks = Vec::new();
+    for record in &index_block.records {
+        data_blocks.push(read_data_block(&mut reader, codecs, record.block_handle)?);
+    }
+
+    let storage_kind = decoder::detect_storage_kind_with_keys(
+        file_path,
+        data_blocks
+            .iter()
+            .flat_map(|block| block.records.iter().map(|record| record.key.as_slice())),
+    );
+
+    // IndexedDB uses its own comparator (idb_cmp1), all others compare bytewise
+    if storage_kind != decoder::StorageKind::IndexedDb {
+        check_key_order(&mut data_blocks);
+    }
+
+    Ok(LdbFile {
+        footer,
+        meta_index_block,
+        index_block,
+        meta_blocks,
+        data_blocks,
+        storage_kind,
+    })
+}
+
+// -----------------------------------------------------------------------------
+pub(crate) fn read_index_block(
+    reader: &mut (impl Read + Seek),
+    codecs: &CodecRegistry,
+    block_handle: BlockHandle,
+) -> io::Result<IndexBlock> {
+    let raw_block = read_raw_block(reader, codecs, block_handle.offset, block_handle.size)?;
+    // without the index block no other block can be found
+    if let Some(e) = &raw_block.decompress_error {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Block at offset {}: {}", block_handle.offset, e),
+        ));
+    }
+    let records = read_block_data_kvs(&raw_block.data)?
         .into_iter()
         .map(|entry| {
             let block_handle =
@@ -116,23 +208,32 @@ pub fn parse_file(file_path: &str) -> io::Result<LdbFile> {
         })
         .collect();
 
-    let meta_index_block = IndexBlock {
-        raw_block: meta_index_raw,
-        records: meta_index_records,
-        block_handle: footer.meta_index_handle,
-    };
+    Ok(IndexBlock {
+        raw_block,
+        records,
+        block_handle,
+    })
+}
 
-    // Meta Blocks
+pub(crate) fn read_meta_blocks(
+    reader: &mut (impl Read + Seek),
+    codecs: &CodecRegistry,
+    meta_index_block: &IndexBlock,
+) -> io::Result<Vec<MetaBlock>> {
     let mut meta_blocks = Vec::new();
     for record in &meta_index_block.records {
         let meta_raw = read_raw_block(
-            &mut reader,
+            reader,
+            codecs,
             record.block_handle.offset,
             record.block_handle.size,
         )?;
         let name = decoder::bytes_to_ascii_with_hex(&record.key);
-        let bloom_filter = if name == "filter.leveldb.BuiltinBloomFilter2" {
-            Some(parse_bloom_filter_block(&meta_raw.data)?)
+        // a filter that fails to parse is left out, the data blocks do not need it
+        let bloom_filter = if name == "filter.leveldb.BuiltinBloomFilter2"
+            && meta_raw.decompress_error.is_none()
+        {
+            parse_bloom_filter_block(&meta_raw.data).ok()
         } else {
             None
         };
@@ -144,75 +245,52 @@ pub fn parse_file(file_path: &str) -> io::Result<LdbFile> {
             bloom_filter,
         });
     }
+    Ok(meta_blocks)
+}
 
-    // Index Block
-    let index_raw = read_raw_block(
-        &mut reader,
-        footer.index_handle.offset,
-        footer.index_handle.size,
-    )?;
-    let index_kvs = read_block_data_kvs(&index_raw.data)?;
-    let index_records = index_kvs
-        .into_iter()
-        .map(|entry| {
-            let block_handle =
-                parse_block_handle(&entry.value).unwrap_or(BlockHandle { offset: 0, size: 0 });
-            IndexRecord {
-                key: entry.key.clone(),
-                block_handle,
-                entry,
-            }
-        })
-        .collect();
-
-    let index_block = IndexBlock {
-        raw_block: index_raw,
-        records: index_records,
-        block_handle: footer.index_handle,
+pub(crate) fn read_data_block(
+    reader: &mut (impl Read + Seek),
+    codecs: &CodecRegistry,
+    block_handle: BlockHandle,
+) -> io::Result<DataBlock> {
+    let data_raw = read_raw_block(reader, codecs, block_handle.offset, block_handle.size)?;
+
+    // a block that fails to decompress or parse is kept without records, the
+    // other blocks of the table are still read
+    let contents = match &data_raw.decompress_error {
+        Some(e) => Err(e.clone()),
+        None => read_block_contents(&data_raw.data).map_err(|e| e.to_string()),
+    };
+    let (records, restart_points, issues) = match contents {
+        Ok(contents) => {
+            let issues = check_block_structure(&contents);
+            let records = contents.entries.into_iter().map(data_record).collect();
+            (records, contents.restart_points, issues)
+        }
+        Err(e) => (Vec::new(), Vec::new(), vec![e]),
     };
 
-    // Data Blocks
-    let mut data_blocks = Vec::new();
-    for record in &index_block.records {
-        let data_raw = read_raw_block(
-            &mut reader,
-            record.block_handle.offset,
-            record.block_handle.size,
-        )?;
-        let data_kvs = read_block_data_kvs(&data_raw.data)?;
-        let data_records = data_kvs
-            .into_iter()
-            .map(|entry| {
-                let (key, state, seq) = utils::decode_key(&entry.key).unwrap_or((Vec::new(), 0, 0));
-                DataRecord {
-                    seq,
-                    state,
-                    key,
-                    value: entry.value.clone(),
-                    entry,
-                }
-            })
-            .collect();
+    Ok(DataBlock {
+        raw_block: data_raw,
+        records,
+        block_handle,
+        restart_points,
+        issues,
+    })
+}
 
-        data_blocks.push(DataBlock {
-            raw_block: data_raw,
-            records: data_records,
-            block_handle: record.block_handle,
-        });
+pub(crate) fn data_record(entry: KeyValPair) -> DataRecord {
+    let (key, state, seq) = utils::decode_key(&entry.key).unwrap_or((Vec::new(), 0, 0));
+    DataRecord {
+        seq,
+        state,
+        key,
+        value: entry.value.clone(),
+        entry,
     }
-
-    Ok(LdbFile {
-        footer,
-        meta_index_block,
-        index_block,
-        meta_blocks,
-        data_blocks,
-        storage_kind,
-    })
 }
 
-// -----------------------------------------------------------------------------
-fn read_footer(reader: &mut (impl Read + Seek)) -> io::Result<Footer> {
+pub(crate) fn read_footer(reader: &mut (impl Read + Seek)) -> io::Result<Footer> {
     let offset = reader.seek(io::SeekFrom::End(-48))?;
 
     let meta_index_handle = BlockHandle {
@@ -241,7 +319,12 @@ fn read_footer(reader: &mut (impl Read + Seek)) -> io::Result<Footer> {
     })
 }
 
-fn read_raw_block(reader: &mut (impl Read + Seek), offset: u64, size: u64) -> io::Result<RawBlock> {
+pub(crate) fn read_raw_block(
+    reader: &mut (impl Read + Seek),
+    codecs: &CodecRegistry,
+    offset: u64,
+    size: u64,
+) -> io::Result<RawBlock> {
     reader.seek(io::SeekFrom::Start(offset))?;
 
     // data
@@ -257,49 +340,180 @@ fn read_raw_block(reader: &mut (impl Read + Seek), offset: u64, size: u64) -> io
     // verify crc
     let crc_valid = utils::crc_verified(crc, &data, compression_type, true);
 
-    // decompress data if needed
-    let data = if compression_type == 0x1 {
-        snap::raw::Decoder::new().decompress_vec(&data)?
-    } else if compression_type == 0x2 {
-        // NOTE: not tested
-        zstd::decode_all(data.as_slice())?
-    } else {
-        data
+    // decompress data, the raw bytes are kept if that fails
+    let (data, decompress_error) = match codecs.decompress(compression_type, &data) {
+        Ok(decompressed) => (decompressed, None),
+        Err(e) => (data, Some(e.to_string())),
     };
+    let codec_name = codecs
+        .get(compression_type)
+        .map(|codec| codec.name().to_string())
+        .unwrap_or_default();
 
     Ok(RawBlock {
         data,
         compression_type,
+        codec_name,
         crc,
         crc_valid,
+        decompress_error,
     })
 }
 
-fn read_block_data_kvs(data: &[u8]) -> io::Result<Vec<KeyValPair>> {
+// compares internal keys: user key ascending (bytewise), then sequence number
+// and type descending
+pub fn compare_internal_keys(a: &[u8], b: &[u8]) -> Ordering {
+    if a.len() < 8 || b.len() < 8 {
+        return a.cmp(b);
+    }
+    let (a_user_key, a_trailer) = a.split_at(a.len() - 8);
+    let (b_user_key, b_trailer) = b.split_at(b.len() - 8);
+    a_user_key.cmp(b_user_key).then_with(|| {
+        let a_trailer = u64::from_le_bytes(a_trailer.try_into().unwrap());
+        let b_trailer = u64::from_le_bytes(b_trailer.try_into().unwrap());
+        b_trailer.cmp(&a_trailer)
+    })
+}
+
+struct BlockContents {
+    entries: Vec<KeyValPair>,
+    restart_points: Vec<u32>,
+    restart_array_offset: u64,
+    end_offset: u64, // where entry parsing stopped
+}
+
+// restart points and the offset of the restart array (= end of the entries)
+pub(crate) fn read_restart_array(data: &[u8]) -> io::Result<(Vec<u32>, u64)> {
     let mut cursor = Cursor::new(data);
 
     cursor.seek(io::SeekFrom::End(-4))?;
     let restart_arr_len = cursor.read_u32::<LittleEndian>()?;
-    let restart_array_offset = cursor.seek(io::SeekFrom::End(-4 - (4 * restart_arr_len as i64)))?;
+    let restart_arr_size = 4 * restart_arr_len as u64 + 4;
+    if restart_arr_size > data.len() as u64 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Restart array (Count: {}) exceeds block", restart_arr_len),
+        ));
+    }
+    let restart_array_offset = data.len() as u64 - restart_arr_size;
+
+    cursor.seek(io::SeekFrom::Start(restart_array_offset))?;
+    let mut restart_points = Vec::with_capacity(restart_arr_len as usize);
+    for _ in 0..restart_arr_len {
+        restart_points.push(cursor.read_u32::<LittleEndian>()?);
+    }
+
+    Ok((restart_points, restart_array_offset))
+}
+
+fn read_block_data_kvs(data: &[u8]) -> io::Result<Vec<KeyValPair>> {
+    Ok(read_block_contents(data)?.entries)
+}
+
+fn read_block_contents(data: &[u8]) -> io::Result<BlockContents> {
+    let (restart_points, restart_array_offset) = read_restart_array(data)?;
+    let mut cursor = Cursor::new(data);
 
     let mut entries = Vec::new();
     let mut prev_key = Vec::new();
 
     cursor.seek(io::SeekFrom::Start(0))?;
     while cursor.position() < restart_array_offset {
+        let entry_offset = cursor.position();
         match read_block_entry(&mut cursor, &prev_key) {
-            Ok(entry) => {
+            Ok(entry) if cursor.position() <= restart_array_offset => {
                 prev_key = entry.key.clone();
                 entries.push(entry);
             }
-            Err(_) => break,
+            _ => {
+                cursor.set_position(entry_offset);
+                break;
+            }
         }
     }
 
-    Ok(entries)
+    Ok(BlockContents {
+        entries,
+        restart_points,
+        restart_array_offset,
+        end_offset: cursor.position(),
+    })
 }
 
-fn read_block_entry(cursor: &mut Cursor<&[u8]>, prev_key: &[u8]) -> io::Result<KeyValPair> {
+// restart points must be ascending and start entries without shared key bytes
+fn check_block_structure(contents: &BlockContents) -> Vec<String> {
+    let mut issues = Vec::new();
+
+    if contents.end_offset != contents.restart_array_offset {
+        issues.push(format!(
+            "Entries end at offset {}, restart array starts at {}",
+            contents.end_offset, contents.restart_array_offset
+        ));
+    }
+    if contents.restart_points.is_empty() && !contents.entries.is_empty() {
+        issues.push("No restart points".to_string());
+    }
+    if let Some(&first) = contents.restart_points.first()
+        && first != 0
+    {
+        issues.push(format!("First restart point {} is not 0", first));
+    }
+
+    let mut prev_point = None;
+    for (idx, &point) in contents.restart_points.iter().enumerate() {
+        if prev_point.is_some_and(|prev| point <= prev) {
+            issues.push(format!("Restart point {} ({}) not ascending", idx, point));
+        }
+        prev_point = Some(point);
+
+        // entries are in file order, sorted by offset
+        let entry = contents
+            .entries
+            .binary_search_by_key(&(point as u64), |entry| entry.entry_offset)
+            .ok()
+            .map(|index| &contents.entries[index]);
+        match entry {
+            Some(entry) if entry.shared_len != 0 => issues.push(format!(
+                "Restart point {} ({}) has shared key length {}",
+                idx, point, entry.shared_len
+            )),
+            Some(_) => {}
+            None => issues.push(format!(
+                "Restart point {} ({}) is not an entry boundary",
+                idx, point
+            )),
+        }
+    }
+
+    issues
+}
+
+// keys must be ascending within and across data blocks
+fn check_key_order(data_blocks: &mut [DataBlock]) {
+    let mut prev_key: Option<Vec<u8>> = None;
+    for data_block in data_blocks.iter_mut() {
+        let mut issues = Vec::new();
+        for (idx, record) in data_block.records.iter().enumerate() {
+            if let Some(prev) = &prev_key
+                && compare_internal_keys(prev, &record.entry.key) != Ordering::Less
+            {
+                issues.push(if idx == 0 {
+                    "First key not after the last key of the previous block".to_string()
+                } else {
+                    format!("Key of entry {} not after the previous key", idx)
+                });
+            }
+            prev_key = Some(record.entry.key.clone());
+        }
+        data_block.issues.extend(issues);
+    }
+}
+
+pub(crate) fn read_block_entry(
+    cursor: &mut Cursor<&[u8]>,
+    prev_key: &[u8],
+) -> io::Result<KeyValPair> {
+    let entry_offset = cursor.position();
     let shared_len = utils::read_varint(cursor)? as usize;
     let inline_len = utils::read_varint(cursor)? as usize;
     let value_len = utils::read_varint(cursor)? as usize;
@@ -324,6 +538,7 @@ fn read_block_entry(cursor: &mut Cursor<&[u8]>, prev_key: &[u8]) -> io::Result<K
     cursor.read_exact(&mut value)?;
 
     Ok(KeyValPair {
+        entry_offset,
         shared_len,
         inline_len,
         value_len,
@@ -334,22 +549,141 @@ fn read_block_entry(cursor: &mut Cursor<&[u8]>, prev_key: &[u8]) -> io::Result<K
     })
 }
 
-fn parse_bloom_filter_block(data: &[u8]) -> io::Result<BloomFilter> {
+// also usable on filter blocks carved from fragments
+pub fn parse_bloom_filter_block(data: &[u8]) -> io::Result<BloomFilter> {
     let mut cursor = Cursor::new(data);
 
     cursor.seek(io::SeekFrom::End(-5))?;
     let array_offset = cursor.read_u32::<LittleEndian>()?;
     let base_log = cursor.read_u8()?;
-    let filter_data = data[0..array_offset as usize].to_vec();
+    // LevelDB writes 11 (one filter per 2 KiB), larger values are corrupt
+    if base_log > MAX_FILTER_BASE_LOG {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Invalid filter base log {}", base_log),
+        ));
+    }
+    let filter_data = data
+        .get(0..array_offset as usize)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Invalid filter array offset"))?
+        .to_vec();
+
+    // offset array between the filters and the trailing array offset / base log
+    let filter_offsets = data
+        .get(array_offset as usize..data.len() - 5)
+        .unwrap_or_default()
+        .chunks_exact(4)
+        .map(|offset| u32::from_le_bytes(offset.try_into().unwrap()))
+        .collect();
 
     Ok(BloomFilter {
         filter_data,
         array_offset,
         base_log,
+        filter_offsets,
     })
 }
 
-fn parse_block_handle(data: &[u8]) -> io::Result<BlockHandle> {
+// hash of LevelDB util/hash.cc with the seed of the bloom filter policy
+pub fn bloom_hash(key: &[u8]) -> u32 {
+    const SEED: u32 = 0xbc9f1d34;
+    const M: u32 = 0xc6a4a793;
+    const R: u32 = 24;
+
+    let mut h = SEED ^ (key.len() as u32).wrapping_mul(M);
+
+    let mut chunks = key.chunks_exact(4);
+    for chunk in &mut chunks {
+        let w = u32::from_le_bytes(chunk.try_into().unwrap());
+        h = h.wrapping_add(w).wrapping_mul(M);
+        h ^= h >> 16;
+    }
+
+    let rest = chunks.remainder();
+    if !rest.is_empty() {
+        for (i, &b) in rest.iter().enumerate().rev() {
+            h = h.wrapping_add((b as u32) << (8 * i));
+        }
+        h = h.wrapping_mul(M);
+        h ^= h >> R;
+    }
+
+    h
+}
+
+// single filter: bit array followed by the number of probes
+pub fn bloom_filter_may_match(filter: &[u8], key: &[u8]) -> bool {
+    if filter.len() < 2 {
+        return false;
+    }
+
+    let bits = ((filter.len() - 1) * 8) as u32;
+    let k = filter[filter.len() - 1];
+    if k > 30 {
+        // reserved for potentially new encodings, treated as a match
+        return true;
+    }
+
+    let mut h = bloom_hash(key);
+    let delta = h.rotate_right(17);
+    for _ in 0..k {
+        let bit_pos = h % bits;
+        if filter[(bit_pos / 8) as usize] & (1 << (bit_pos % 8)) == 0 {
+            return false;
+        }
+        h = h.wrapping_add(delta);
+    }
+
+    true
+}
+
+pub fn bloom_filter(ldb: &LdbFile) -> Option<&BloomFilter> {
+    ldb.meta_blocks
+        .iter()
+        .find_map(|meta_block| meta_block.bloom_filter.as_ref())
+}
+
+// false only if the user key is definitely not in the table
+pub fn may_contain(ldb: &LdbFile, key: &[u8]) -> bool {
+    match bloom_filter(ldb) {
+        Some(bloom_filter) => ldb
+            .data_blocks
+            .iter()
+            .any(|block| bloom_filter.may_contain(block.block_handle.offset, key)),
+        None => true,
+    }
+}
+
+// every stored key must be contained in the filter of its data block, a
+// mismatch points to a modified table
+pub fn verify_bloom_filter(ldb: &LdbFile) -> Vec<BloomMismatch> {
+    let Some(bloom_filter) = bloom_filter(ldb) else {
+        return Vec::new();
+    };
+
+    let mut mismatches = Vec::new();
+    for data_block in &ldb.data_blocks {
+        let block_offset = data_block.block_handle.offset;
+        // blocks without filter are not excluded
+        let Some(filter_index) = bloom_filter.filter_index(block_offset) else {
+            continue;
+        };
+        for record in &data_block.records {
+            if !bloom_filter.may_contain(block_offset, &record.key) {
+                mismatches.push(BloomMismatch {
+                    block_offset,
+                    filter_index,
+                    seq: record.seq,
+                    key: record.key.clone(),
+                });
+            }
+        }
+    }
+
+    mismatches
+}
+
+pub(crate) fn parse_block_handle(data: &[u8]) -> io::Result<BlockHandle> {
     let mut cursor = Cursor::new(data);
     let offset = utils::read_varint(&mut cursor)?;
     let size = utils::read_varint(&mut cursor)?;
@@ -358,6 +692,7 @@ fn parse_block_handle(data: &[u8]) -> io::Result<BlockHandle> {
 // -----------------------------------------------------------------------------
 pub mod display {
     use super::*;
+    use crate::decoder_registry::{DecodeContext, DecoderRegistry};
 
     pub fn print_all(ldb: &LdbFile) -> io::Result<()> {
         print_footer(&ldb.footer)?;
@@ -373,6 +708,10 @@ pub mod display {
             print_data_block(data_block, idx)?;
         }
 
+        if bloom_filter(ldb).is_some() {
+            print_bloom_filter_check(ldb)?;
+        }
+
         Ok(())
     }
 
@@ -486,6 +825,14 @@ pub mod display {
         )?;
         print_raw_block_info(&data_block.raw_block)?;
         print_block_data_info(&data_block.raw_block.data)?;
+        writeln!(
+            io::stdout(),
+            "RestartPoints: {:?}",
+            data_block.restart_points
+        )?;
+        for issue in &data_block.issues {
+            writeln!(io::stdout(), "Issue: {}", issue)?;
+        }
 
         for (record_idx, record) in data_block.records.iter().enumerate() {
             print_data_record(record, record_idx, data_block.block_handle.offset)?;
@@ -530,16 +877,12 @@ pub mod display {
     }
 
     pub fn print_raw_block_info(raw_block: &RawBlock) -> io::Result<()> {
-        match raw_block.compression_type {
-            0x0 => writeln!(io::stdout(), "CompressionType: 0 (NoCompression)")?,
-            0x1 => writeln!(io::stdout(), "CompressionType: 1 (Snappy)")?,
-            0x2 => writeln!(io::stdout(), "CompressionType: 2 (Zstd)")?,
-            _ => writeln!(
-                io::stdout(),
-                "CompressionType: {} (Unknown)",
-                raw_block.compression_type
-            )?,
-        }
+        writeln!(
+            io::stdout(),
+            "CompressionType: {} ({})",
+            raw_block.compression_type,
+            raw_block.codec_name
+        )?;
 
         if raw_block.crc_valid {
             writeln!(io::stdout(), "CRC32C: {:02X} (verified)", raw_block.crc)?;
@@ -551,6 +894,10 @@ pub mod display {
             )?;
         }
 
+        if let Some(e) = &raw_block.decompress_error {
+            writeln!(io::stdout(), "Decompression failed: {}", e)?;
+        }
+
         Ok(())
     }
 
@@ -583,26 +930,75 @@ pub mod display {
         )?;
         writeln!(io::stdout(), "ArrayOffset: {}", bloom_filter.array_offset)?;
         writeln!(io::stdout(), "BaseLog: {}", bloom_filter.base_log)?;
+        writeln!(
+            io::stdout(),
+            "Filters: {} (Offsets: {:?})",
+            bloom_filter.filter_offsets.len(),
+            bloom_filter.filter_offsets
+        )?;
+        Ok(())
+    }
+
+    pub fn print_bloom_filter_check(ldb: &LdbFile) -> io::Result<()> {
+        writeln!(
+            io::stdout(),
+            "\n************* Bloom Filter Check *************"
+        )?;
+
+        let mismatches = verify_bloom_filter(ldb);
+        if mismatches.is_empty() {
+            writeln!(io::stdout(), "All keys contained in their block filter")?;
+        }
+        for mismatch in &mismatches {
+            writeln!(
+                io::stdout(),
+                "Mismatch: Block (Offset: {}), Filter {}, Seq: {}, Key: '{}'",
+                mismatch.block_offset,
+                mismatch.filter_index,
+                mismatch.seq,
+                decoder::bytes_to_hex(&mismatch.key)
+            )?;
+        }
+
         Ok(())
     }
     // -----------------------------------------------------------------------------
     pub fn print_csv(ldb: &LdbFile) -> io::Result<()> {
+        print_csv_filtered(ldb, "", &RecordFilter::default())
+    }
+
+    // file_path selects path based decoders of the registry
+    pub fn print_csv_filtered(
+        ldb: &LdbFile,
+        file_path: &str,
+        filter: &RecordFilter,
+    ) -> io::Result<()> {
+        let registry = DecoderRegistry::default();
+
         // Header
         writeln!(io::stdout(), "\"seq\",\"state\",\"key\",\"value\"")?;
 
         for data_block in &ldb.data_blocks {
             for record in &data_block.records {
+                if !filter.matches_raw(record.seq, record.state, &record.key) {
+                    continue;
+                }
+                let (key_str, value_str, kind_str) = registry.decode_kv(&DecodeContext {
+                    path: file_path,
+                    storage_kind: ldb.storage_kind,
+                    key: &record.key,
+                    value: (record.state != 0).then_some(record.value.as_slice()),
+                });
+                if !filter.matches_decoded(&key_str, &value_str, &kind_str) {
+                    continue;
+                }
+
                 let state_str = match record.state {
                     0 => "Deleted",
                     1 => "Live",
                     _ => "Unknown",
                 };
 
-                let (key_str, value_str, _) = decoder::decode_kv(
-                    ldb.storage_kind,
-                    &record.key,
-                    (record.state != 0).then_some(record.value.as_slice()),
-                );
                 let key_str = key_str.replace("\"", "\"\"");
                 let value_str = value_str.replace("\"", "\"\"");
 
@@ -623,8 +1019,43 @@ pub mod display {
 
 pub mod export {
     use super::*;
+    use crate::decoder_registry::{DecodeContext, DecoderRegistry};
 
     pub fn csv_string(ldb: &LdbFile, filename: &str, file_path: &str, hex_view: bool) -> String {
+        csv_string_with_registry(
+            ldb,
+            &DecoderRegistry::default(),
+            filename,
+            file_path,
+            hex_view,
+        )
+    }
+
+    pub fn csv_string_with_registry(
+        ldb: &LdbFile,
+        registry: &DecoderRegistry,
+        filename: &str,
+        file_path: &str,
+        hex_view: bool,
+    ) -> String {
+        csv_string_filtered(
+            ldb,
+            registry,
+            &RecordFilter::default(),
+            filename,
+            file_path,
+            hex_view,
+        )
+    }
+
+    pub fn csv_string_filtered(
+        ldb: &LdbFile,
+        registry: &DecoderRegistry,
+        filter: &RecordFilter,
+        filename: &str,
+        file_path: &str,
+        hex_view: bool,
+    ) -> String {
         let mut csv = String::new();
         // Header
         csv.push_str("\"Seq\",\"K\",\"V\",\"Cr\",\"St\",\"BO\",\"C\",\"F\",\"FP\",\"Kind\"\n");
@@ -638,6 +1069,24 @@ pub mod export {
             };
 
             for record in &data_block.records {
+                let ctx = DecodeContext {
+                    path: file_path,
+                    storage_kind: ldb.storage_kind,
+                    key: &record.key,
+                    value: (record.state != 0).then_some(record.value.as_slice()),
+                };
+                if !filter.matches_raw(record.seq, record.state, &record.key) {
+                    continue;
+                }
+                // decoded once for the filter and the output
+                let decoded =
+                    (!hex_view || filter.needs_decoding()).then(|| registry.decode_kv(&ctx));
+                if let Some((key_str, value_str, kind_str)) = &decoded
+                    && !filter.matches_decoded(key_str, value_str, kind_str)
+                {
+                    continue;
+                }
+
                 let state_str = match record.state {
                     0 => "deleted",
                     1 => "live",
@@ -653,11 +1102,8 @@ pub mod export {
                     value_str = decoder::bytes_to_hex_raw(&record.value);
                     kind_str = "".to_string();
                 } else {
-                    (key_str, value_str, kind_str) = decoder::decode_kv(
-                        ldb.storage_kind,
-                        &record.key,
-                        (record.state != 0).then_some(record.value.as_slice()),
-                    );
+                    (key_str, value_str, kind_str) =
+                        decoded.unwrap_or_else(|| registry.decode_kv(&ctx));
                     key_str = key_str.replace("\"", "\"\"");
                     value_str = value_str.replace("\"", "\"\"");
                 }
@@ -681,3 +1127,246 @@ pub mod export {
         csv
     }
 }
+
+// filter of LevelDB's BloomFilterPolicy::CreateFilter
+#[cfg(test)]
+pub(crate) fn create_filter(keys: &[&[u8]], bits_per_key: usize) -> Vec<u8> {
+    let k = ((bits_per_key as f64 * 0.69) as u8).clamp(1, 30);
+    let bits = (keys.len() * bits_per_key).max(64);
+    let bytes = bits.div_ceil(8);
+    let bits = (bytes * 8) as u32;
+
+    let mut filter = vec![0u8; bytes];
+    for key in keys {
+        let mut h = bloom_hash(key);
+        let delta = h.rotate_right(17);
+        for _ in 0..k {
+            let bit_pos = h % bits;
+            filter[(bit_pos / 8) as usize] |= 1 << (bit_pos % 8);
+            h = h.wrapping_add(delta);
+        }
+    }
+    filter.push(k);
+    filter
+}
+
+// uncompressed table of (user key, seq, state, value) records in internal key
+// order, with a bloom filter per 2 KiB of data blocks
+#[cfg(test)]
+pub(crate) fn write_table(
+    path: &std::path::Path,
+    records: &[(&[u8], u64, u8, &[u8])],
+    entries_per_block: usize,
+    restart_interval: usize,
+) -> io::Result<()> {
+    fn put_varint(out: &mut Vec<u8>, mut value: u64) {
+        while value >= 0x80 {
+            out.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        out.push(value as u8);
+    }
+
+    fn build_block(entries: &[(Vec<u8>, Vec<u8>)], restart_interval: usize) -> Vec<u8> {
+        let mut block = Vec::new();
+        let mut restart_points = Vec::new();
+        let mut prev_key: &[u8] = &[];
+        for (idx, (key, value)) in entries.iter().enumerate() {
+            let shared = if idx % restart_interval == 0 {
+                restart_points.push(block.len() as u32);
+                0
+            } else {
+                key.iter().zip(prev_key).take_while(|(a, b)| a == b).count()
+            };
+            put_varint(&mut block, shared as u64);
+            put_varint(&mut block, (key.len() - shared) as u64);
+            put_varint(&mut block, value.len() as u64);
+            block.extend(&key[shared..]);
+            block.extend(value);
+            prev_key = key;
+        }
+        if restart_points.is_empty() {
+            restart_points.push(0);
+        }
+        for point in &restart_points {
+            block.extend(point.to_le_bytes());
+        }
+        block.extend((restart_points.len() as u32).to_le_bytes());
+        block
+    }
+
+    // block + compression type + masked CRC, returns the encoded handle
+    fn append_block(file: &mut Vec<u8>, block: &[u8]) -> Vec<u8> {
+        let mut handle = Vec::new();
+        put_varint(&mut handle, file.len() as u64);
+        put_varint(&mut handle, block.len() as u64);
+
+        let mut crc_data = block.to_vec();
+        crc_data.push(0x0);
+        let crc = crc32c::crc32c(&crc_data);
+        let masked_crc = crc.rotate_right(15).wrapping_add(0xa282ead8);
+        file.extend(block);
+        file.push(0x0);
+        file.extend(masked_crc.to_le_bytes());
+        handle
+    }
+
+    let mut file = Vec::new();
+    let mut index_entries = Vec::new();
+    let mut filter_keys: Vec<Vec<&[u8]>> = Vec::new();
+    for chunk in records.chunks(entries_per_block) {
+        let entries: Vec<(Vec<u8>, Vec<u8>)> = chunk
+            .iter()
+            .map(|&(user_key, seq, state, value)| {
+                let mut key = user_key.to_vec();
+                key.extend(((seq << 8) | state as u64).to_le_bytes());
+                (key, value.to_vec())
+            })
+            .collect();
+
+        let filter_index = file.len() >> 11;
+        filter_keys.resize(filter_keys.len().max(filter_index + 1), Vec::new());
+        filter_keys[filter_index].extend(chunk.iter().map(|record| record.0));
+
+        let handle = append_block(&mut file, &build_block(&entries, restart_interval));
+        // last key of the block as separator
+        index_entries.push((entries[entries.len() - 1].0.clone(), handle));
+    }
+
+    let mut filter_block = Vec::new();
+    let mut filter_offsets = Vec::new();
+    for keys in &filter_keys {
+        filter_offsets.push(filter_block.len() as u32);
+        if !keys.is_empty() {
+            filter_block.extend(create_filter(keys, 10));
+        }
+    }
+    let array_offset = filter_block.len() as u32;
+    for offset in filter_offsets {
+        filter_block.extend(offset.to_le_bytes());
+    }
+    filter_block.extend(array_offset.to_le_bytes());
+    filter_block.push(11);
+    let filter_handle = append_block(&mut file, &filter_block);
+
+    let meta_index = build_block(
+        &[(
+            b"filter.leveldb.BuiltinBloomFilter2".to_vec(),
+            filter_handle,
+        )],
+        1,
+    );
+    let meta_index_handle = append_block(&mut file, &meta_index);
+    let index_handle = append_block(&mut file, &build_block(&index_entries, 1));
+
+    let mut footer = [meta_index_handle, index_handle].concat();
+    footer.resize(40, 0);
+    footer.extend([0x57, 0xFB, 0x80, 0x8B, 0x24, 0x75, 0x47, 0xDB]);
+    file.extend(footer);
+
+    std::fs::write(path, file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bloom_hash_values_of_hash_test_cc() {
+        assert_eq!(bloom_hash(&[]), 0xbc9f1d34);
+        assert_eq!(bloom_hash(&[0x62]), 0xef1345c4);
+        assert_eq!(bloom_hash(&[0xc3, 0x97]), 0x5b663814);
+        assert_eq!(bloom_hash(&[0xe2, 0x99, 0xa5]), 0x323c078f);
+        assert_eq!(bloom_hash(&[0xe1, 0x80, 0xb9, 0x32]), 0xed21633a);
+    }
+
+    #[test]
+    fn bloom_filter_contains_its_keys() {
+        let keys: [&[u8]; 3] = [b"hello", b"world", b"key00042"];
+        let filter = create_filter(&keys, 10);
+        for key in keys {
+            assert!(bloom_filter_may_match(&filter, key));
+        }
+        assert!(!bloom_filter_may_match(&filter, b"x"));
+    }
+
+    #[test]
+    fn bloom_filter_special_cases() {
+        // too short, no key matches
+        assert!(!bloom_filter_may_match(&[0x06], b"hello"));
+        // reserved probe count, every key matches
+        assert!(bloom_filter_may_match(&[0x00, 0x00, 31], b"hello"));
+    }
+
+    // data block of (shared, non shared key, value) entries and a restart array
+    fn build_block(entries: &[(usize, &[u8], &[u8])], restart_points: &[u32]) -> Vec<u8> {
+        let mut block = Vec::new();
+        for &(shared, key_delta, value) in entries {
+            block.extend([shared as u8, key_delta.len() as u8, value.len() as u8]);
+            block.extend(key_delta);
+            block.extend(value);
+        }
+        for point in restart_points {
+            block.extend(point.to_le_bytes());
+        }
+        block.extend((restart_points.len() as u32).to_le_bytes());
+        block
+    }
+
+    // entries at offsets 0, 8, 15 and 23
+    const ENTRIES: [(usize, &[u8], &[u8]); 4] = [
+        (0, b"key1", b"a"),
+        (3, b"2xy", b"b"),
+        (0, b"key3", b"c"),
+        (3, b"4xy", b"d"),
+    ];
+
+    fn block_issues(restart_points: &[u32]) -> Vec<String> {
+        let block = build_block(&ENTRIES, restart_points);
+        check_block_structure(&read_block_contents(&block).unwrap())
+    }
+
+    #[test]
+    fn restart_points_at_entry_boundaries() {
+        assert!(block_issues(&[0, 15]).is_empty());
+        assert!(block_issues(&[0]).is_empty());
+    }
+
+    #[test]
+    fn restart_point_out_of_range() {
+        assert_eq!(
+            block_issues(&[0, 100]),
+            vec!["Restart point 1 (100) is not an entry boundary"]
+        );
+    }
+
+    #[test]
+    fn restart_point_unaligned() {
+        assert_eq!(
+            block_issues(&[0, 16]),
+            vec!["Restart point 1 (16) is not an entry boundary"]
+        );
+        assert_eq!(
+            block_issues(&[3, 15]),
+            vec![
+                "First restart point 3 is not 0",
+                "Restart point 0 (3) is not an entry boundary"
+            ]
+        );
+    }
+
+    #[test]
+    fn restart_point_with_shared_key_bytes() {
+        assert_eq!(
+            block_issues(&[0, 8]),
+            vec!["Restart point 1 (8) has shared key length 3"]
+        );
+        assert_eq!(
+            block_issues(&[15, 0]),
+            vec![
+                "First restart point 15 is not 0",
+                "Restart point 1 (0) not ascending"
+            ]
+        );
+    }
+}
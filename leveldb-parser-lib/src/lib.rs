@@ -1,6 +1,28 @@
+pub mod bedrock;
+pub mod bitcoin;
+pub mod bloom_recovery;
+pub mod chromium_stores;
+pub mod codec;
+pub mod database;
 pub mod decoder;
+pub mod decoder_registry;
+pub mod extension_settings;
+pub mod inventory;
+pub mod ioc_sweep;
+pub mod key_timeline;
 pub mod ldb_parser;
+pub mod local_storage;
 pub mod log_parser;
 pub mod log_text_parser;
 pub mod manifest_parser;
+pub mod nbt;
+pub mod protobuf;
+pub mod record_filter;
+pub mod residue;
+pub mod search;
+pub mod service_worker;
+pub mod session_storage;
+pub mod snapshot_diff;
+pub mod table_reader;
 pub mod utils;
+pub mod verify;
@@ -0,0 +1,368 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::database::DbRecord;
+use crate::decoder;
+
+// Local Storage keys:
+//   VERSION                        => schema version
+//   META:<origin>                  => last modified time and size (protobuf)
+//   METAACCESS:<origin>            => last access time (protobuf)
+//   _<origin>\x00<flag><key>       => <flag><value>
+// -----------------------------------------------------------------------------
+pub struct LocalStorage {
+    pub origins: Vec<Origin>,
+}
+
+pub struct Origin {
+    pub origin: String,
+    pub last_modified: Option<i64>,
+    pub last_accessed: Option<i64>,
+    pub size: Option<u64>,
+    // latest META: / METAACCESS: row is a deletion, the times and size above
+    // are from the last live row
+    pub meta_deleted: bool,
+    pub meta_access_deleted: bool,
+    pub entries: Vec<LocalStorageEntry>,
+    pub live_keys: usize,
+    pub live_bytes: u64,
+}
+
+pub struct LocalStorageEntry {
+    pub key: String,
+    pub value: String,
+    pub value_size: usize,
+    pub seq: u64,
+    pub state: u8,
+    pub file_path: String,
+}
+
+// -----------------------------------------------------------------------------
+pub fn build(records: &[DbRecord]) -> LocalStorage {
+    let mut origins: BTreeMap<String, Origin> = BTreeMap::new();
+    // latest version of every key per origin: (seq, live, size)
+    let mut latest: HashMap<(String, Vec<u8>), (u64, bool, u64)> = HashMap::new();
+    // seqs of the META: / METAACCESS: rows per origin
+    let mut meta_seqs: HashMap<String, MetaSeqs> = HashMap::new();
+
+    for record in records {
+        let key = record.key.as_slice();
+
+        if let Some(origin) = key.strip_prefix(b"META:") {
+            let origin = decoder::bytes_to_utf8_lossy(origin);
+            let seqs = meta_seqs.entry(origin.clone()).or_default();
+            let entry = origin_entry(&mut origins, &origin);
+            if record.seq >= seqs.meta_latest {
+                seqs.meta_latest = record.seq;
+                entry.meta_deleted = record.value.is_none();
+            }
+            if let Some(v_bytes) = record.value.as_deref()
+                && record.seq >= seqs.meta_live
+            {
+                seqs.meta_live = record.seq;
+                let (time, size) = decoder::parse_local_storage_meta(v_bytes);
+                entry.last_modified = time.map(|ts| ts as i64);
+                entry.size = size;
+            }
+        } else if let Some(origin) = key.strip_prefix(b"METAACCESS:") {
+            let origin = decoder::bytes_to_utf8_lossy(origin);
+            let seqs = meta_seqs.entry(origin.clone()).or_default();
+            let entry = origin_entry(&mut origins, &origin);
+            if record.seq >= seqs.access_latest {
+                seqs.access_latest = record.seq;
+                entry.meta_access_deleted = record.value.is_none();
+            }
+            if let Some(v_bytes) = record.value.as_deref()
+                && record.seq >= seqs.access_live
+            {
+                seqs.access_live = record.seq;
+                let (time, _) = decoder::parse_local_storage_meta(v_bytes);
+                entry.last_accessed = time.map(|ts| ts as i64);
+            }
+        } else if let Some(rest) = key.strip_prefix(b"_") {
+            let Some(delim_pos) = rest.iter().position(|&b| b == 0x00) else {
+                continue;
+            };
+            let origin = decoder::bytes_to_utf8_lossy(&rest[..delim_pos]);
+            let entry_key = &rest[delim_pos + 1..];
+
+            let value_size = record.value.as_ref().map_or(0, |v| v.len());
+            let value = match record.value.as_deref() {
+                Some(v_bytes) => decoder::decode_local_storage_value(v_bytes),
+                None => String::new(),
+            };
+
+            let version = latest
+                .entry((origin.clone(), entry_key.to_vec()))
+                .or_insert((0, false, 0));
+            if record.seq >= version.0 {
+                *version = (
+                    record.seq,
+                    record.state != 0,
+                    (entry_key.len() + value_size) as u64,
+                );
+            }
+
+            origin_entry(&mut origins, &origin)
+                .entries
+                .push(LocalStorageEntry {
+                    key: decoder::decode_local_storage_value(entry_key),
+                    value,
+                    value_size,
+                    seq: record.seq,
+                    state: record.state,
+                    file_path: record.file_path.clone(),
+                });
+        }
+    }
+
+    // totals over the latest version of every key
+    for ((origin, _), (_, live, size)) in &latest {
+        if *live && let Some(entry) = origins.get_mut(origin) {
+            entry.live_keys += 1;
+            entry.live_bytes += size;
+        }
+    }
+
+    LocalStorage {
+        origins: origins.into_values().collect(),
+    }
+}
+
+#[derive(Default)]
+struct MetaSeqs {
+    meta_latest: u64,
+    meta_live: u64,
+    access_latest: u64,
+    access_live: u64,
+}
+
+fn origin_entry<'a>(origins: &'a mut BTreeMap<String, Origin>, origin: &str) -> &'a mut Origin {
+    origins.entry(origin.to_string()).or_insert_with(|| Origin {
+        origin: origin.to_string(),
+        last_modified: None,
+        last_accessed: None,
+        size: None,
+        meta_deleted: false,
+        meta_access_deleted: false,
+        entries: Vec::new(),
+        live_keys: 0,
+        live_bytes: 0,
+    })
+}
+
+// -----------------------------------------------------------------------------
+pub mod export {
+    use super::*;
+
+    pub fn origins_csv_string(local_storage: &LocalStorage) -> String {
+        let mut csv = String::new();
+        // Header
+        csv.push_str(
+            "\"Origin\",\"Modified\",\"Accessed\",\"Size\",\"MetaDeleted\",\"AccessDeleted\",\"LiveKeys\",\"LiveBytes\",\"Records\"\n",
+        );
+
+        for origin in &local_storage.origins {
+            csv.push_str(&format!(
+                "\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\"\n",
+                origin.origin.replace("\"", "\"\""),
+                origin
+                    .last_modified
+                    .map(decoder::format_chrome_timestamp)
+                    .unwrap_or_default(),
+                origin
+                    .last_accessed
+                    .map(decoder::format_chrome_timestamp)
+                    .unwrap_or_default(),
+                origin.size.map(|s| s.to_string()).unwrap_or_default(),
+                origin.meta_deleted,
+                origin.meta_access_deleted,
+                origin.live_keys,
+                origin.live_bytes,
+                origin.entries.len(),
+            ));
+        }
+
+        csv
+    }
+
+    pub fn entries_csv_string(local_storage: &LocalStorage) -> String {
+        let mut csv = String::new();
+        // Header
+        csv.push_str("\"Seq\",\"Origin\",\"K\",\"V\",\"St\",\"Modified\",\"Accessed\",\"FP\"\n");
+
+        for origin in &local_storage.origins {
+            for entry in &origin.entries {
+                let state_str = match entry.state {
+                    0 => "deleted",
+                    1 => "live",
+                    _ => "unknown",
+                };
+
+                csv.push_str(&format!(
+                    "\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\"\n",
+                    entry.seq,
+                    origin.origin.replace("\"", "\"\""),
+                    entry.key.replace("\"", "\"\""),
+                    entry.value.replace("\"", "\"\""),
+                    state_str,
+                    origin
+                        .last_modified
+                        .map(decoder::format_chrome_timestamp)
+                        .unwrap_or_default(),
+                    origin
+                        .last_accessed
+                        .map(decoder::format_chrome_timestamp)
+                        .unwrap_or_default(),
+                    entry.file_path,
+                ));
+            }
+        }
+
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(key: &[u8], seq: u64, value: Option<&[u8]>) -> DbRecord {
+        DbRecord {
+            seq,
+            state: value.is_some() as u8,
+            key: key.to_vec(),
+            value: value.map(|value| value.to_vec()),
+            file_path: "000003.log".to_string(),
+            block_offset: 0,
+            key_offset: 0,
+        }
+    }
+
+    fn entry_key(origin: &str, key: &str) -> Vec<u8> {
+        [b"_", origin.as_bytes(), b"\x00\x01", key.as_bytes()].concat()
+    }
+
+    fn varint(mut value: u64) -> Vec<u8> {
+        let mut out = Vec::new();
+        while value >= 0x80 {
+            out.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        out.push(value as u8);
+        out
+    }
+
+    // protobuf: field 1 time, field 2 size
+    fn meta(time: u64, size: Option<u64>) -> Vec<u8> {
+        let mut out = vec![0x08];
+        out.extend(varint(time));
+        if let Some(size) = size {
+            out.push(0x10);
+            out.extend(varint(size));
+        }
+        out
+    }
+
+    #[test]
+    fn entries_grouped_by_origin() {
+        let records = [
+            record(b"VERSION", 1, Some(b"1")),
+            record(&entry_key("https://b.example", "x"), 2, Some(b"\x011")),
+            record(
+                &entry_key("https://a.example", "theme"),
+                3,
+                Some(b"\x01dark"),
+            ),
+            record(&entry_key("https://a.example", "lang"), 4, Some(b"\x01en")),
+        ];
+
+        let local_storage = build(&records);
+
+        let origins: Vec<&str> = local_storage
+            .origins
+            .iter()
+            .map(|origin| origin.origin.as_str())
+            .collect();
+        assert_eq!(origins, vec!["https://a.example", "https://b.example"]);
+
+        let a = &local_storage.origins[0];
+        let entries: Vec<(&str, &str)> = a
+            .entries
+            .iter()
+            .map(|entry| (entry.key.as_str(), entry.value.as_str()))
+            .collect();
+        assert_eq!(entries, vec![("theme", "dark"), ("lang", "en")]);
+    }
+
+    #[test]
+    fn meta_rows_joined_to_origin() {
+        let records = [
+            record(&entry_key("https://a.example", "k"), 1, Some(b"\x01v")),
+            record(b"META:https://a.example", 2, Some(&meta(100, Some(42)))),
+            record(b"META:https://a.example", 5, Some(&meta(300, Some(64)))),
+            // older row read later (from a table) does not win
+            record(b"META:https://a.example", 3, Some(&meta(200, Some(50)))),
+            record(b"METAACCESS:https://a.example", 4, Some(&meta(400, None))),
+        ];
+
+        let origin = &build(&records).origins[0];
+        assert_eq!(origin.last_modified, Some(300));
+        assert_eq!(origin.size, Some(64));
+        assert_eq!(origin.last_accessed, Some(400));
+        assert!(!origin.meta_deleted);
+        assert!(!origin.meta_access_deleted);
+    }
+
+    #[test]
+    fn deleted_meta_rows_keep_last_values() {
+        let records = [
+            record(b"META:https://a.example", 2, Some(&meta(100, Some(42)))),
+            record(b"METAACCESS:https://a.example", 3, Some(&meta(150, None))),
+            record(b"META:https://a.example", 6, None),
+            record(b"METAACCESS:https://a.example", 7, None),
+        ];
+
+        let origin = &build(&records).origins[0];
+        assert_eq!(origin.last_modified, Some(100));
+        assert_eq!(origin.size, Some(42));
+        assert_eq!(origin.last_accessed, Some(150));
+        assert!(origin.meta_deleted);
+        assert!(origin.meta_access_deleted);
+
+        // a live row newer than the deletion clears the flag again
+        let records = [
+            record(b"META:https://a.example", 6, None),
+            record(b"META:https://a.example", 8, Some(&meta(500, Some(1)))),
+        ];
+        let origin = &build(&records).origins[0];
+        assert_eq!(origin.last_modified, Some(500));
+        assert!(!origin.meta_deleted);
+    }
+
+    #[test]
+    fn totals_over_latest_version_of_every_key() {
+        let records = [
+            // "theme": latest version is live, 6 + 5 bytes (flags included)
+            record(
+                &entry_key("https://a.example", "theme"),
+                1,
+                Some(b"\x01light"),
+            ),
+            record(
+                &entry_key("https://a.example", "theme"),
+                4,
+                Some(b"\x01dark"),
+            ),
+            // "gone": latest version is deleted
+            record(&entry_key("https://a.example", "gone"), 2, Some(b"\x01x")),
+            record(&entry_key("https://a.example", "gone"), 3, None),
+            // "lang": 5 + 3 bytes
+            record(&entry_key("https://a.example", "lang"), 5, Some(b"\x01en")),
+        ];
+
+        let origin = &build(&records).origins[0];
+        assert_eq!(origin.entries.len(), 5);
+        assert_eq!(origin.live_keys, 2);
+        assert_eq!(origin.live_bytes, (6 + 5) + (5 + 3));
+    }
+}
@@ -4,6 +4,7 @@ use std::io::{self, BufReader, Cursor, Read, Seek, SeekFrom, Write};
 use byteorder::{LittleEndian, ReadBytesExt};
 
 use crate::decoder;
+use crate::record_filter::RecordFilter;
 use crate::utils;
 
 // -----------------------------------------------------------------------------
@@ -50,8 +51,6 @@ pub fn parse_file(file_path: &str) -> io::Result<LogFile> {
     let file_size = file.metadata()?.len();
     let mut reader = BufReader::new(file);
 
-    let storage_kind = decoder::detect_storage_kind(file_path);
-
     let mut blocks = Vec::new();
     let mut batches = Vec::new();
     let mut partial_block_data = Vec::new();
@@ -77,21 +76,17 @@ pub fn parse_file(file_path: &str) -> io::Result<LogFile> {
                 partial_block_data.clear();
                 partial_block_data.extend_from_slice(&block.data);
             }
-            3 => {
+            3 if !partial_block_data.is_empty() => {
                 // Middle Block
-                if !partial_block_data.is_empty() {
-                    partial_block_data.extend_from_slice(&block.data);
-                }
+                partial_block_data.extend_from_slice(&block.data);
             }
-            4 => {
+            4 if !partial_block_data.is_empty() => {
                 // Last Block
-                if !partial_block_data.is_empty() {
-                    partial_block_data.extend_from_slice(&block.data);
-                    if let Ok(batch) = parse_batch(&partial_block_data, first_block_offset) {
-                        batches.push(batch);
-                    }
-                    partial_block_data.clear();
+                partial_block_data.extend_from_slice(&block.data);
+                if let Ok(batch) = parse_batch(&partial_block_data, first_block_offset) {
+                    batches.push(batch);
                 }
+                partial_block_data.clear();
             }
             _ => {} // Zero Block or Unknown Type => ignore
         }
@@ -99,6 +94,13 @@ pub fn parse_file(file_path: &str) -> io::Result<LogFile> {
         blocks.push(block);
     }
 
+    let storage_kind = decoder::detect_storage_kind_with_keys(
+        file_path,
+        batches
+            .iter()
+            .flat_map(|batch| batch.records.iter().map(|record| record.key.as_slice())),
+    );
+
     Ok(LogFile {
         blocks,
         batches,
@@ -264,6 +266,7 @@ fn read_entry_with_offset(
 // -----------------------------------------------------------------------------
 pub mod display {
     use super::*;
+    use crate::decoder_registry::{DecodeContext, DecoderRegistry};
     // -----------------------------------------------------------------------------
     pub fn print_all(log: &LogFile) -> io::Result<()> {
         let mut current_batch_idx = 0;
@@ -272,19 +275,10 @@ pub mod display {
             print_block_header(block, i as u64 + 1)?;
 
             match block.block_type {
-                1 => {
-                    // Full block
-                    if current_batch_idx < log.batches.len() {
-                        print_batch(&log.batches[current_batch_idx])?;
-                        current_batch_idx += 1;
-                    }
-                }
-                4 => {
-                    // Last block
-                    if current_batch_idx < log.batches.len() {
-                        print_batch(&log.batches[current_batch_idx])?;
-                        current_batch_idx += 1;
-                    }
+                1 | 4 if current_batch_idx < log.batches.len() => {
+                    // Full block | Last block
+                    print_batch(&log.batches[current_batch_idx])?;
+                    current_batch_idx += 1;
                 }
                 _ => {} // other block types
             }
@@ -383,19 +377,41 @@ pub mod display {
     }
     // -----------------------------------------------------------------------------
     pub fn print_csv(log: &LogFile) -> io::Result<()> {
+        print_csv_filtered(log, "", &RecordFilter::default())
+    }
+
+    // file_path selects path based decoders of the registry
+    pub fn print_csv_filtered(
+        log: &LogFile,
+        file_path: &str,
+        filter: &RecordFilter,
+    ) -> io::Result<()> {
+        let registry = DecoderRegistry::default();
+
         // Header
         writeln!(io::stdout(), "\"seq\",\"state\",\"key\",\"value\"")?;
 
         for batch in &log.batches {
             for record in &batch.records {
+                if !filter.matches_raw(record.seq, record.state, &record.key) {
+                    continue;
+                }
+                let (key_str, value_str, kind_str) = registry.decode_kv(&DecodeContext {
+                    path: file_path,
+                    storage_kind: log.storage_kind,
+                    key: &record.key,
+                    value: record.value.as_deref(),
+                });
+                if !filter.matches_decoded(&key_str, &value_str, &kind_str) {
+                    continue;
+                }
+
                 let state_str = match record.state {
                     0 => "Deleted",
                     1 => "Live",
                     _ => "Unknown",
                 };
 
-                let (key_str, value_str, _) =
-                    decoder::decode_kv(log.storage_kind, &record.key, record.value.as_deref());
                 let key_str = key_str.replace("\"", "\"\"");
                 let value_str = value_str.replace("\"", "\"\"");
 
@@ -416,8 +432,43 @@ pub mod display {
 
 pub mod export {
     use super::*;
+    use crate::decoder_registry::{DecodeContext, DecoderRegistry};
 
     pub fn csv_string(log: &LogFile, filename: &str, file_path: &str, hex_view: bool) -> String {
+        csv_string_with_registry(
+            log,
+            &DecoderRegistry::default(),
+            filename,
+            file_path,
+            hex_view,
+        )
+    }
+
+    pub fn csv_string_with_registry(
+        log: &LogFile,
+        registry: &DecoderRegistry,
+        filename: &str,
+        file_path: &str,
+        hex_view: bool,
+    ) -> String {
+        csv_string_filtered(
+            log,
+            registry,
+            &RecordFilter::default(),
+            filename,
+            file_path,
+            hex_view,
+        )
+    }
+
+    pub fn csv_string_filtered(
+        log: &LogFile,
+        registry: &DecoderRegistry,
+        filter: &RecordFilter,
+        filename: &str,
+        file_path: &str,
+        hex_view: bool,
+    ) -> String {
         let mut csv = String::new();
         // Header
         csv.push_str("\"Seq\",\"K\",\"V\",\"Cr\",\"St\",\"BO\",\"C\",\"F\",\"FP\",\"Kind\"\n");
@@ -433,6 +484,24 @@ pub mod export {
             };
 
             for record in &batch.records {
+                let ctx = DecodeContext {
+                    path: file_path,
+                    storage_kind: log.storage_kind,
+                    key: &record.key,
+                    value: record.value.as_deref(),
+                };
+                if !filter.matches_raw(record.seq, record.state, &record.key) {
+                    continue;
+                }
+                // decoded once for the filter and the output
+                let decoded =
+                    (!hex_view || filter.needs_decoding()).then(|| registry.decode_kv(&ctx));
+                if let Some((key_str, value_str, kind_str)) = &decoded
+                    && !filter.matches_decoded(key_str, value_str, kind_str)
+                {
+                    continue;
+                }
+
                 let state_str = match record.state {
                     0 => "deleted",
                     1 => "live",
@@ -449,7 +518,7 @@ pub mod export {
                     kind_str = "".to_string();
                 } else {
                     (key_str, value_str, kind_str) =
-                        decoder::decode_kv(log.storage_kind, &record.key, record.value.as_deref());
+                        decoded.unwrap_or_else(|| registry.decode_kv(&ctx));
                     key_str = key_str.replace("\"", "\"\"");
                     value_str = value_str.replace("\"", "\"\"");
                 }
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::{self, BufReader, Cursor, Seek};
 
@@ -47,6 +48,33 @@ pub enum ManifestEntry {
     Unknown(u8),
 }
 
+// table of the current version
+#[derive(Clone)]
+pub struct TableFile {
+    pub level: u64,
+    pub file_no: u64,
+    pub file_size: u64,
+    pub sm_key: Vec<u8>,
+    pub sm_seq: u64,
+    pub sm_state: u8,
+    pub lg_key: Vec<u8>,
+    pub lg_seq: u64,
+    pub lg_state: u8,
+}
+
+// database state after applying all entry sets of the manifest
+#[derive(Default)]
+pub struct VersionState {
+    pub comparator: Option<String>,
+    pub log_number: Option<u64>,
+    pub prev_log_number: Option<u64>,
+    pub next_file_number: Option<u64>,
+    pub last_seq: Option<u64>,
+    pub live_files: Vec<TableFile>, // ordered by level and file number
+    pub added_files: Vec<u64>,      // file numbers of all AddFile entries
+    pub removed_files: Vec<u64>,    // file numbers of all RemoveFile entries
+}
+
 pub fn parse_file(file_path: &str) -> io::Result<ManifestFile> {
     let file = File::open(file_path)?;
     let file_size = file.metadata()?.len();
@@ -92,6 +120,65 @@ pub fn parse_file(file_path: &str) -> io::Result<ManifestFile> {
     Ok(ManifestFile { blocks, entries })
 }
 
+// -----------------------------------------------------------------------------
+pub fn replay(manifest: &ManifestFile) -> VersionState {
+    let mut state = VersionState::default();
+    let mut live_files = BTreeMap::new();
+
+    for entry in manifest.entries.iter().flat_map(|set| set.entries.iter()) {
+        match entry {
+            ManifestEntry::Comparator(name) => {
+                state.comparator = Some(decoder::bytes_to_utf8_lossy(name))
+            }
+            ManifestEntry::LogNumber(log_no) => state.log_number = Some(*log_no),
+            ManifestEntry::PrevLogNumber(prev_log_no) => state.prev_log_number = Some(*prev_log_no),
+            ManifestEntry::NextFileNumber(next_file_no) => {
+                state.next_file_number = Some(*next_file_no)
+            }
+            ManifestEntry::LastSeq(last_seq) => state.last_seq = Some(*last_seq),
+            ManifestEntry::RemoveFile { level, file_no } => {
+                live_files.remove(&(*level, *file_no));
+                state.removed_files.push(*file_no);
+            }
+            ManifestEntry::AddFile {
+                level,
+                file_no,
+                file_size,
+                sm_key,
+                sm_seq,
+                sm_state,
+                lg_key,
+                lg_seq,
+                lg_state,
+            } => {
+                live_files.insert(
+                    (*level, *file_no),
+                    TableFile {
+                        level: *level,
+                        file_no: *file_no,
+                        file_size: *file_size,
+                        sm_key: sm_key.clone(),
+                        sm_seq: *sm_seq,
+                        sm_state: *sm_state,
+                        lg_key: lg_key.clone(),
+                        lg_seq: *lg_seq,
+                        lg_state: *lg_state,
+                    },
+                );
+                state.added_files.push(*file_no);
+            }
+            ManifestEntry::CompactPointer { .. } | ManifestEntry::Unknown(_) => {}
+        }
+    }
+
+    state.live_files = live_files.into_values().collect();
+    state.added_files.sort_unstable();
+    state.added_files.dedup();
+    state.removed_files.sort_unstable();
+    state.removed_files.dedup();
+    state
+}
+
 fn parse_entries(data: &[u8], offset: u64) -> io::Result<ManifestEntrySet> {
     let mut result_entries = Vec::new();
     let mut cursor = Cursor::new(data);
@@ -183,19 +270,10 @@ pub mod display {
             log_parser::display::print_block_header(block, i as u64 + 1)?;
 
             match block.block_type {
-                1 => {
-                    // Full block
-                    if current_entry_idx < manifest.entries.len() {
-                        print_entries(&manifest.entries[current_entry_idx])?;
-                        current_entry_idx += 1;
-                    }
-                }
-                4 => {
-                    // Last block
-                    if current_entry_idx < manifest.entries.len() {
-                        print_entries(&manifest.entries[current_entry_idx])?;
-                        current_entry_idx += 1;
-                    }
+                1 | 4 if current_entry_idx < manifest.entries.len() => {
+                    // Full block | Last block
+                    print_entries(&manifest.entries[current_entry_idx])?;
+                    current_entry_idx += 1;
                 }
                 _ => {} // other block types
             }
@@ -457,3 +535,96 @@ pub mod export {
         csv
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add_file(level: u64, file_no: u64) -> ManifestEntry {
+        ManifestEntry::AddFile {
+            level,
+            file_no,
+            file_size: 1000 + file_no,
+            sm_key: b"a".to_vec(),
+            sm_seq: 1,
+            sm_state: 1,
+            lg_key: b"z".to_vec(),
+            lg_seq: 9,
+            lg_state: 1,
+        }
+    }
+
+    fn manifest(sets: Vec<Vec<ManifestEntry>>) -> ManifestFile {
+        ManifestFile {
+            blocks: Vec::new(),
+            entries: sets
+                .into_iter()
+                .enumerate()
+                .map(|(idx, entries)| ManifestEntrySet {
+                    entries,
+                    offset: idx as u64 * 100,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn replay_applies_edits_in_order() {
+        let manifest = manifest(vec![
+            vec![
+                ManifestEntry::Comparator(b"leveldb.BytewiseComparator".to_vec()),
+                ManifestEntry::LogNumber(3),
+                ManifestEntry::NextFileNumber(4),
+                ManifestEntry::LastSeq(10),
+            ],
+            vec![add_file(0, 5), add_file(0, 6), ManifestEntry::LogNumber(7)],
+            vec![
+                ManifestEntry::RemoveFile {
+                    level: 0,
+                    file_no: 5,
+                },
+                ManifestEntry::RemoveFile {
+                    level: 0,
+                    file_no: 6,
+                },
+                add_file(1, 8),
+                ManifestEntry::LastSeq(42),
+            ],
+        ]);
+
+        let state = replay(&manifest);
+        assert_eq!(
+            state.comparator.as_deref(),
+            Some("leveldb.BytewiseComparator")
+        );
+        assert_eq!(state.log_number, Some(7));
+        assert_eq!(state.next_file_number, Some(4));
+        assert_eq!(state.last_seq, Some(42));
+        assert_eq!(state.prev_log_number, None);
+
+        let live: Vec<(u64, u64)> = state
+            .live_files
+            .iter()
+            .map(|table| (table.level, table.file_no))
+            .collect();
+        assert_eq!(live, vec![(1, 8)]);
+        assert_eq!(state.live_files[0].file_size, 1008);
+        assert_eq!(state.added_files, vec![5, 6, 8]);
+        assert_eq!(state.removed_files, vec![5, 6]);
+    }
+
+    #[test]
+    fn replay_removes_only_the_given_level() {
+        let manifest = manifest(vec![vec![
+            add_file(0, 5),
+            ManifestEntry::RemoveFile {
+                level: 1,
+                file_no: 5,
+            },
+        ]]);
+
+        let state = replay(&manifest);
+        assert_eq!(state.live_files.len(), 1);
+        assert_eq!(state.live_files[0].level, 0);
+    }
+}
@@ -0,0 +1,240 @@
+use crate::decoder;
+
+// little-endian NBT (Named Binary Tag) of Minecraft Bedrock, values may hold
+// several root tags in a row (e.g. all block entities of a chunk)
+// -----------------------------------------------------------------------------
+const MAX_DEPTH: usize = 512;
+
+const TAG_END: u8 = 0;
+const TAG_BYTE: u8 = 1;
+const TAG_SHORT: u8 = 2;
+const TAG_INT: u8 = 3;
+const TAG_LONG: u8 = 4;
+const TAG_FLOAT: u8 = 5;
+const TAG_DOUBLE: u8 = 6;
+const TAG_BYTE_ARRAY: u8 = 7;
+const TAG_STRING: u8 = 8;
+const TAG_LIST: u8 = 9;
+const TAG_COMPOUND: u8 = 10;
+const TAG_INT_ARRAY: u8 = 11;
+const TAG_LONG_ARRAY: u8 = 12;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum NbtTag {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<u8>),
+    String(String),
+    List(Vec<NbtTag>),
+    Compound(Vec<(String, NbtTag)>),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+}
+
+// -----------------------------------------------------------------------------
+// all named root tags, None if the bytes are not completely NBT
+pub fn decode_nbt(bytes: &[u8]) -> Option<Vec<(String, NbtTag)>> {
+    let mut i = 0;
+    let mut roots = Vec::new();
+    while i < bytes.len() {
+        let tag_type = read_u8(bytes, &mut i)?;
+        if tag_type == TAG_END {
+            return None;
+        }
+        let name = read_string(bytes, &mut i)?;
+        let tag = read_payload(bytes, &mut i, tag_type, 0)?;
+        roots.push((name, tag));
+    }
+    (!roots.is_empty()).then_some(roots)
+}
+
+pub fn roots_to_string(roots: &[(String, NbtTag)]) -> String {
+    let items: Vec<String> = roots
+        .iter()
+        .map(|(name, tag)| {
+            if name.is_empty() {
+                tag_to_string(tag)
+            } else {
+                format!("\"{}\": {}", name, tag_to_string(tag))
+            }
+        })
+        .collect();
+    items.join(", ")
+}
+
+pub fn tag_to_string(tag: &NbtTag) -> String {
+    match tag {
+        NbtTag::Byte(v) => format!("{}b", v),
+        NbtTag::Short(v) => format!("{}s", v),
+        NbtTag::Int(v) => v.to_string(),
+        NbtTag::Long(v) => format!("{}L", v),
+        NbtTag::Float(v) => format!("{}f", v),
+        NbtTag::Double(v) => format!("{}d", v),
+        NbtTag::ByteArray(bytes) => format!("[B; {}]", decoder::bytes_to_hex(bytes)),
+        NbtTag::String(s) => format!("\"{}\"", s),
+        NbtTag::List(tags) => {
+            let items: Vec<String> = tags.iter().map(tag_to_string).collect();
+            format!("[{}]", items.join(", "))
+        }
+        NbtTag::Compound(entries) => {
+            let items: Vec<String> = entries
+                .iter()
+                .map(|(name, tag)| format!("\"{}\": {}", name, tag_to_string(tag)))
+                .collect();
+            format!("{{{}}}", items.join(", "))
+        }
+        NbtTag::IntArray(values) => {
+            let items: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+            format!("[I; {}]", items.join(", "))
+        }
+        NbtTag::LongArray(values) => {
+            let items: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+            format!("[L; {}]", items.join(", "))
+        }
+    }
+}
+
+// render a value as NBT text, None if it is not NBT
+pub fn decode_nbt_value(bytes: &[u8]) -> Option<String> {
+    decode_nbt(bytes).map(|roots| roots_to_string(&roots))
+}
+
+// -----------------------------------------------------------------------------
+fn read_payload(bytes: &[u8], i: &mut usize, tag_type: u8, depth: usize) -> Option<NbtTag> {
+    if depth > MAX_DEPTH {
+        return None;
+    }
+
+    let tag = match tag_type {
+        TAG_BYTE => NbtTag::Byte(read_u8(bytes, i)? as i8),
+        TAG_SHORT => NbtTag::Short(i16::from_le_bytes(read_array(bytes, i)?)),
+        TAG_INT => NbtTag::Int(i32::from_le_bytes(read_array(bytes, i)?)),
+        TAG_LONG => NbtTag::Long(i64::from_le_bytes(read_array(bytes, i)?)),
+        TAG_FLOAT => NbtTag::Float(f32::from_le_bytes(read_array(bytes, i)?)),
+        TAG_DOUBLE => NbtTag::Double(f64::from_le_bytes(read_array(bytes, i)?)),
+        TAG_BYTE_ARRAY => {
+            let len = read_len(bytes, i)?;
+            let data = bytes.get(*i..i.checked_add(len)?)?;
+            *i += len;
+            NbtTag::ByteArray(data.to_vec())
+        }
+        TAG_STRING => NbtTag::String(read_string(bytes, i)?),
+        TAG_LIST => {
+            let item_type = read_u8(bytes, i)?;
+            let len = read_len(bytes, i)?;
+            let mut items = Vec::new();
+            for _ in 0..len {
+                items.push(read_payload(bytes, i, item_type, depth + 1)?);
+            }
+            NbtTag::List(items)
+        }
+        TAG_COMPOUND => {
+            let mut entries = Vec::new();
+            loop {
+                let entry_type = read_u8(bytes, i)?;
+                if entry_type == TAG_END {
+                    break;
+                }
+                let name = read_string(bytes, i)?;
+                entries.push((name, read_payload(bytes, i, entry_type, depth + 1)?));
+            }
+            NbtTag::Compound(entries)
+        }
+        TAG_INT_ARRAY => {
+            let len = read_len(bytes, i)?;
+            let mut values = Vec::new();
+            for _ in 0..len {
+                values.push(i32::from_le_bytes(read_array(bytes, i)?));
+            }
+            NbtTag::IntArray(values)
+        }
+        TAG_LONG_ARRAY => {
+            let len = read_len(bytes, i)?;
+            let mut values = Vec::new();
+            for _ in 0..len {
+                values.push(i64::from_le_bytes(read_array(bytes, i)?));
+            }
+            NbtTag::LongArray(values)
+        }
+        _ => return None,
+    };
+
+    Some(tag)
+}
+
+fn read_u8(bytes: &[u8], i: &mut usize) -> Option<u8> {
+    let b = *bytes.get(*i)?;
+    *i += 1;
+    Some(b)
+}
+
+fn read_array<const N: usize>(bytes: &[u8], i: &mut usize) -> Option<[u8; N]> {
+    let data = bytes.get(*i..i.checked_add(N)?)?;
+    *i += N;
+    data.try_into().ok()
+}
+
+// signed 32-bit lengths, negative lengths are invalid
+fn read_len(bytes: &[u8], i: &mut usize) -> Option<usize> {
+    let len = i32::from_le_bytes(read_array(bytes, i)?);
+    let len = usize::try_from(len).ok()?;
+    // every item takes at least one byte
+    (len <= bytes.len()).then_some(len)
+}
+
+fn read_string(bytes: &[u8], i: &mut usize) -> Option<String> {
+    let len = u16::from_le_bytes(read_array(bytes, i)?) as usize;
+    let data = bytes.get(*i..i.checked_add(len)?)?;
+    *i += len;
+    Some(decoder::bytes_to_utf8_lossy(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // unnamed compound root {"a": 5, "s": "hi"}
+    const COMPOUND: [u8; 20] = [
+        0x0A, 0x00, 0x00, // compound, empty name
+        0x03, 0x01, 0x00, b'a', 0x05, 0x00, 0x00, 0x00, // int "a"
+        0x08, 0x01, 0x00, b's', 0x02, 0x00, b'h', b'i', // string "s"
+        0x00, // end
+    ];
+
+    #[test]
+    fn compound_root() {
+        let roots = decode_nbt(&COMPOUND).unwrap();
+        assert_eq!(
+            roots,
+            vec![(
+                String::new(),
+                NbtTag::Compound(vec![
+                    ("a".to_string(), NbtTag::Int(5)),
+                    ("s".to_string(), NbtTag::String("hi".to_string())),
+                ])
+            )]
+        );
+        assert_eq!(
+            decode_nbt_value(&COMPOUND).unwrap(),
+            "{\"a\": 5, \"s\": \"hi\"}"
+        );
+    }
+
+    #[test]
+    fn several_roots() {
+        let mut bytes = COMPOUND.to_vec();
+        bytes.extend_from_slice(&COMPOUND);
+        assert_eq!(decode_nbt(&bytes).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn not_nbt() {
+        assert_eq!(decode_nbt(&[]), None);
+        assert_eq!(decode_nbt(&[0x00]), None);
+        assert_eq!(decode_nbt(&COMPOUND[..COMPOUND.len() - 1]), None);
+    }
+}
@@ -0,0 +1,274 @@
+use crate::decoder;
+
+// schemaless protobuf decoding, nested messages / strings / packed varints are
+// detected heuristically since the schema is unknown
+// -----------------------------------------------------------------------------
+const MAX_FIELD_NUMBER: u64 = (1 << 29) - 1;
+const MAX_DEPTH: usize = 32;
+
+// plausible Chrome timestamps (2000-01-01 .. 2100-01-01, microseconds since 1601)
+const CHROME_TIME_MIN: u64 = 12_591_158_400_000_000;
+const CHROME_TIME_MAX: u64 = 15_747_004_800_000_000;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProtoField {
+    pub field_number: u64,
+    pub wire_type: u8,
+    pub value: ProtoValue,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ProtoValue {
+    Varint(u64),
+    Fixed64(u64),
+    Fixed32(u32),
+    String(String),
+    Message(Vec<ProtoField>),
+    Packed(Vec<u64>),
+    Bytes(Vec<u8>),
+    Group(Vec<ProtoField>),
+}
+
+// -----------------------------------------------------------------------------
+// decode a complete message, None if the bytes are not a valid message
+pub fn decode_message(bytes: &[u8]) -> Option<Vec<ProtoField>> {
+    decode_fields(bytes, 0)
+}
+
+// render a value as protobuf text, hex escaped if it is not a valid message
+pub fn decode_protobuf(bytes: &[u8]) -> String {
+    match decode_message(bytes) {
+        Some(fields) if !fields.is_empty() => fields_to_string(&fields),
+        _ => decoder::bytes_to_hex(bytes),
+    }
+}
+
+pub fn fields_to_string(fields: &[ProtoField]) -> String {
+    let items: Vec<String> = fields
+        .iter()
+        .map(|field| format!("{}: {}", field.field_number, value_to_string(&field.value)))
+        .collect();
+    format!("{{{}}}", items.join(", "))
+}
+
+pub fn value_to_string(value: &ProtoValue) -> String {
+    match value {
+        ProtoValue::Varint(v) => {
+            if (CHROME_TIME_MIN..CHROME_TIME_MAX).contains(v) {
+                format!("{} ({})", v, decoder::format_chrome_timestamp(*v as i64))
+            } else {
+                v.to_string()
+            }
+        }
+        ProtoValue::Fixed64(v) => v.to_string(),
+        ProtoValue::Fixed32(v) => v.to_string(),
+        ProtoValue::String(s) => format!("\"{}\"", s),
+        ProtoValue::Message(fields) | ProtoValue::Group(fields) => fields_to_string(fields),
+        ProtoValue::Packed(values) => {
+            let items: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+            format!("[{}]", items.join(", "))
+        }
+        ProtoValue::Bytes(bytes) => decoder::bytes_to_hex(bytes),
+    }
+}
+
+// first field with the given number
+pub fn find_field(fields: &[ProtoField], field_number: u64) -> Option<&ProtoValue> {
+    fields
+        .iter()
+        .find(|field| field.field_number == field_number)
+        .map(|field| &field.value)
+}
+
+// -----------------------------------------------------------------------------
+fn decode_fields(bytes: &[u8], depth: usize) -> Option<Vec<ProtoField>> {
+    let mut i = 0;
+    let mut fields = Vec::new();
+    // open groups: (field number, fields before the group)
+    let mut groups: Vec<(u64, Vec<ProtoField>)> = Vec::new();
+
+    while i < bytes.len() {
+        let tag = read_varint(bytes, &mut i)?;
+        let field_number = tag >> 3;
+        let wire_type = (tag & 0x07) as u8;
+        if field_number == 0 || field_number > MAX_FIELD_NUMBER {
+            return None;
+        }
+
+        let value = match wire_type {
+            0 => ProtoValue::Varint(read_varint(bytes, &mut i)?),
+            1 => {
+                let data = bytes.get(i..i + 8)?;
+                i += 8;
+                ProtoValue::Fixed64(u64::from_le_bytes(data.try_into().ok()?))
+            }
+            2 => {
+                let len = read_varint(bytes, &mut i)? as usize;
+                let data = bytes.get(i..i.checked_add(len)?)?;
+                i += len;
+                decode_length_delimited(data, depth)
+            }
+            3 => {
+                // start group
+                groups.push((field_number, std::mem::take(&mut fields)));
+                continue;
+            }
+            4 => {
+                // end group
+                let (group_number, outer_fields) = groups.pop()?;
+                if group_number != field_number {
+                    return None;
+                }
+                let group_fields = std::mem::replace(&mut fields, outer_fields);
+                ProtoValue::Group(group_fields)
+            }
+            5 => {
+                let data = bytes.get(i..i + 4)?;
+                i += 4;
+                ProtoValue::Fixed32(u32::from_le_bytes(data.try_into().ok()?))
+            }
+            _ => return None,
+        };
+
+        fields.push(ProtoField {
+            field_number,
+            wire_type,
+            value,
+        });
+    }
+
+    if !groups.is_empty() {
+        return None;
+    }
+
+    Some(fields)
+}
+
+fn decode_length_delimited(data: &[u8], depth: usize) -> ProtoValue {
+    if data.is_empty() {
+        return ProtoValue::String(String::new());
+    }
+
+    let text = std::str::from_utf8(data).ok().filter(|s| {
+        s.chars()
+            .all(|c| !c.is_control() || matches!(c, '\t' | '\n' | '\r'))
+    });
+
+    // printable text, unless it starts with a control character: a message
+    // with field 1 / wire type 2 first starts with 0x0A ('\n')
+    if let Some(s) = text
+        && !data[0].is_ascii_control()
+    {
+        return ProtoValue::String(s.to_string());
+    }
+
+    // nested message
+    if depth < MAX_DEPTH
+        && let Some(fields) = decode_fields(data, depth + 1)
+        && !fields.is_empty()
+    {
+        return ProtoValue::Message(fields);
+    }
+
+    if let Some(s) = text {
+        return ProtoValue::String(s.to_string());
+    }
+
+    // packed varints, packed fixed32 / fixed64 fields are not detected (any
+    // data of a multiple of 4 / 8 bytes would qualify) and stay bytes
+    if let Some(values) = decode_packed_varints(data) {
+        return ProtoValue::Packed(values);
+    }
+
+    ProtoValue::Bytes(data.to_vec())
+}
+
+// accept only canonical varints (no redundant trailing zero bytes) to keep
+// arbitrary binary data from being taken for packed fields
+fn decode_packed_varints(data: &[u8]) -> Option<Vec<u64>> {
+    let mut i = 0;
+    let mut values = Vec::new();
+    while i < data.len() {
+        let start = i;
+        let value = read_varint(data, &mut i)?;
+        if i - start > 1 && data[i - 1] == 0x00 {
+            return None;
+        }
+        values.push(value);
+    }
+    (values.len() >= 2 && data.len() <= values.len() * 3).then_some(values)
+}
+
+fn read_varint(bytes: &[u8], i: &mut usize) -> Option<u64> {
+    let mut value: u64 = 0;
+    let mut shift = 0u32;
+    loop {
+        let b = *bytes.get(*i)?;
+        *i += 1;
+        value |= ((b & 0x7F) as u64).checked_shl(shift)?;
+        if b & 0x80 == 0 {
+            return Some(value);
+        }
+        shift += 7;
+        if shift >= 64 {
+            return None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn varint_field() {
+        let fields = decode_message(&[0x08, 0x96, 0x01]).unwrap();
+        assert_eq!(
+            fields,
+            vec![ProtoField {
+                field_number: 1,
+                wire_type: 0,
+                value: ProtoValue::Varint(150),
+            }]
+        );
+    }
+
+    #[test]
+    fn string_field() {
+        let fields = decode_message(b"\x12\x07testing").unwrap();
+        assert_eq!(
+            find_field(&fields, 2),
+            Some(&ProtoValue::String("testing".to_string()))
+        );
+    }
+
+    #[test]
+    fn nested_message_starting_with_a_control_byte() {
+        let fields = decode_message(&[0x1A, 0x03, 0x08, 0x96, 0x01]).unwrap();
+        let Some(ProtoValue::Message(nested)) = find_field(&fields, 3) else {
+            panic!("field 3 is not a message");
+        };
+        assert_eq!(find_field(nested, 1), Some(&ProtoValue::Varint(150)));
+    }
+
+    #[test]
+    fn packed_varints() {
+        let fields = decode_message(&[0x22, 0x06, 0x03, 0x8E, 0x02, 0x9E, 0xA7, 0x05]).unwrap();
+        assert_eq!(
+            find_field(&fields, 4),
+            Some(&ProtoValue::Packed(vec![3, 270, 86942]))
+        );
+    }
+
+    #[test]
+    fn invalid_message() {
+        // field number 0
+        assert_eq!(decode_message(&[0x00, 0x01]), None);
+        // length beyond the data
+        assert_eq!(decode_message(&[0x12, 0x05, 0x41]), None);
+        assert_eq!(
+            decode_protobuf(&[0x00, 0x01]),
+            decoder::bytes_to_hex(&[0x00, 0x01])
+        );
+    }
+}
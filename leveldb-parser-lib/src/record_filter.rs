@@ -0,0 +1,319 @@
+use regex::Regex;
+
+use crate::database::DbRecord;
+use crate::decoder::StorageKind;
+use crate::decoder_registry::{DecodeContext, DecoderRegistry};
+
+// restricts records to a key prefix / range, sequence window, state and, on
+// the decoded strings, key / value patterns and kinds
+//   raw conditions are checked first, decoding only happens if needed
+// -----------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StateFilter {
+    #[default]
+    All,
+    Live,
+    Deleted,
+}
+
+#[derive(Default)]
+pub struct RecordFilter {
+    pub prefix: Option<Vec<u8>>,    // raw user key prefix
+    pub start: Option<Vec<u8>>,     // raw user key, inclusive
+    pub end: Option<Vec<u8>>,       // raw user key, exclusive
+    pub key_regex: Option<Regex>,   // on the decoded key
+    pub value_regex: Option<Regex>, // on the decoded value
+    pub seq_min: Option<u64>,
+    pub seq_max: Option<u64>,
+    pub state: StateFilter,
+    pub kinds: Vec<String>, // kind strings of decode_kv, "I" includes IE and II
+}
+
+impl StateFilter {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "all" => Some(StateFilter::All),
+            "live" => Some(StateFilter::Live),
+            "deleted" => Some(StateFilter::Deleted),
+            _ => None,
+        }
+    }
+}
+
+impl RecordFilter {
+    pub fn set_key_regex(&mut self, pattern: &str) -> Result<(), String> {
+        self.key_regex = Some(parse_regex(pattern)?);
+        Ok(())
+    }
+
+    pub fn set_value_regex(&mut self, pattern: &str) -> Result<(), String> {
+        self.value_regex = Some(parse_regex(pattern)?);
+        Ok(())
+    }
+
+    // comma separated kind strings, e.g. "L,S" or "I"
+    pub fn set_kinds(&mut self, spec: &str) {
+        self.kinds = spec
+            .split(',')
+            .map(str::trim)
+            .filter(|kind| !kind.is_empty())
+            .map(|kind| kind.to_ascii_uppercase())
+            .collect();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.needs_decoding()
+            && self.prefix.is_none()
+            && self.start.is_none()
+            && self.end.is_none()
+            && self.seq_min.is_none()
+            && self.seq_max.is_none()
+            && self.state == StateFilter::All
+    }
+
+    pub fn needs_decoding(&self) -> bool {
+        self.key_regex.is_some() || self.value_regex.is_some() || !self.kinds.is_empty()
+    }
+
+    // conditions on the raw record
+    pub fn matches_raw(&self, seq: u64, state: u8, key: &[u8]) -> bool {
+        let state_ok = match self.state {
+            StateFilter::All => true,
+            StateFilter::Live => state == 1,
+            StateFilter::Deleted => state == 0,
+        };
+
+        state_ok
+            && self.seq_min.is_none_or(|min| seq >= min)
+            && self.seq_max.is_none_or(|max| seq <= max)
+            && self
+                .prefix
+                .as_ref()
+                .is_none_or(|prefix| key.starts_with(prefix))
+            && self
+                .start
+                .as_ref()
+                .is_none_or(|start| key >= start.as_slice())
+            && self.end.as_ref().is_none_or(|end| key < end.as_slice())
+    }
+
+    // conditions on the decoded (key, value, kind) strings
+    pub fn matches_decoded(&self, key_str: &str, value_str: &str, kind_str: &str) -> bool {
+        self.key_regex
+            .as_ref()
+            .is_none_or(|regex| regex.is_match(key_str))
+            && self
+                .value_regex
+                .as_ref()
+                .is_none_or(|regex| regex.is_match(value_str))
+            && (self.kinds.is_empty() || self.kinds.iter().any(|kind| kind_matches(kind, kind_str)))
+    }
+
+    // raw conditions, then the decoded ones with the decoder of the record
+    pub fn matches(
+        &self,
+        registry: &DecoderRegistry,
+        ctx: &DecodeContext,
+        seq: u64,
+        state: u8,
+    ) -> bool {
+        if !self.matches_raw(seq, state, ctx.key) {
+            return false;
+        }
+        if !self.needs_decoding() {
+            return true;
+        }
+        let (key_str, value_str, kind_str) = registry.decode_kv(ctx);
+        self.matches_decoded(&key_str, &value_str, &kind_str)
+    }
+
+    pub fn matches_record(
+        &self,
+        registry: &DecoderRegistry,
+        storage_kind: StorageKind,
+        record: &DbRecord,
+    ) -> bool {
+        self.matches(
+            registry,
+            &DecodeContext {
+                path: &record.file_path,
+                storage_kind,
+                key: &record.key,
+                value: record.value.as_deref(),
+            },
+            record.seq,
+            record.state,
+        )
+    }
+
+    // records of a database that pass the filter, order is kept
+    pub fn apply(
+        &self,
+        registry: &DecoderRegistry,
+        storage_kind: StorageKind,
+        records: Vec<DbRecord>,
+    ) -> Vec<DbRecord> {
+        if self.is_empty() {
+            return records;
+        }
+        records
+            .into_iter()
+            .filter(|record| self.matches_record(registry, storage_kind, record))
+            .collect()
+    }
+}
+
+// key argument: "hex:<hex bytes>" or the UTF-8 bytes of the text
+pub fn parse_key_arg(arg: &str) -> Result<Vec<u8>, String> {
+    let Some(hex) = arg.strip_prefix("hex:") else {
+        return Ok(arg.as_bytes().to_vec());
+    };
+
+    let hex: String = hex.chars().filter(|c| !c.is_whitespace()).collect();
+    // from_str_radix alone would accept a sign ("+1")
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("Invalid hex digits in '{}'", arg));
+    }
+    if !hex.len().is_multiple_of(2) {
+        return Err(format!("Odd number of hex digits in '{}'", arg));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&hex[i..i + 2], 16)
+                .map_err(|_| format!("Invalid hex digits in '{}'", arg))
+        })
+        .collect()
+}
+
+// "I" selects all IndexedDB kinds (I, IE, II), like the view filter of the GUI
+fn kind_matches(filter_kind: &str, kind_str: &str) -> bool {
+    if filter_kind == "I" {
+        kind_str.starts_with('I')
+    } else {
+        filter_kind == kind_str
+    }
+}
+
+fn parse_regex(pattern: &str) -> Result<Regex, String> {
+    Regex::new(pattern).map_err(|e| format!("Invalid regex '{}': {}", pattern, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key_range(start: &str, end: &str) -> RecordFilter {
+        RecordFilter {
+            start: Some(start.as_bytes().to_vec()),
+            end: Some(end.as_bytes().to_vec()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn start_inclusive_end_exclusive() {
+        let filter = key_range("b", "d");
+        assert!(!filter.matches_raw(1, 1, b"a"));
+        assert!(filter.matches_raw(1, 1, b"b"));
+        assert!(filter.matches_raw(1, 1, b"c\xFF"));
+        assert!(!filter.matches_raw(1, 1, b"d"));
+        assert!(!filter.matches_raw(1, 1, b"da"));
+    }
+
+    #[test]
+    fn raw_conditions() {
+        let filter = RecordFilter {
+            prefix: Some(b"_https".to_vec()),
+            seq_min: Some(10),
+            seq_max: Some(20),
+            state: StateFilter::Deleted,
+            ..Default::default()
+        };
+        assert!(!filter.is_empty());
+        assert!(!filter.needs_decoding());
+
+        assert!(filter.matches_raw(10, 0, b"_https://a"));
+        assert!(filter.matches_raw(20, 0, b"_https://a"));
+        assert!(!filter.matches_raw(9, 0, b"_https://a"));
+        assert!(!filter.matches_raw(21, 0, b"_https://a"));
+        assert!(!filter.matches_raw(15, 1, b"_https://a"));
+        assert!(!filter.matches_raw(15, 0, b"_http://a"));
+
+        assert!(RecordFilter::default().is_empty());
+        assert!(RecordFilter::default().matches_raw(0, 1, b""));
+    }
+
+    #[test]
+    fn decoded_conditions() {
+        let mut filter = RecordFilter::default();
+        filter.set_key_regex("^theme$").unwrap();
+        filter.set_value_regex("(?i)dark").unwrap();
+        assert!(filter.needs_decoding());
+
+        assert!(filter.matches_decoded("theme", "DARK mode", "L"));
+        assert!(!filter.matches_decoded("theme2", "dark", "L"));
+        assert!(!filter.matches_decoded("theme", "light", "L"));
+
+        assert!(filter.set_key_regex("(").is_err());
+    }
+
+    #[test]
+    fn kind_prefix_matching() {
+        let mut filter = RecordFilter::default();
+        filter.set_kinds(" i , l,");
+        assert_eq!(filter.kinds, vec!["I", "L"]);
+
+        // "I" covers every IndexedDB kind
+        for kind in ["I", "IE", "II"] {
+            assert!(filter.matches_decoded("k", "v", kind));
+        }
+        assert!(filter.matches_decoded("k", "v", "L"));
+        assert!(!filter.matches_decoded("k", "v", "S"));
+
+        // other kinds match exactly
+        filter.set_kinds("IE");
+        assert!(filter.matches_decoded("k", "v", "IE"));
+        assert!(!filter.matches_decoded("k", "v", "I"));
+        assert!(!filter.matches_decoded("k", "v", "II"));
+    }
+
+    #[test]
+    fn key_argument_as_text_or_hex() {
+        assert_eq!(parse_key_arg("key").unwrap(), b"key");
+        assert_eq!(parse_key_arg("hex:").unwrap(), b"");
+        assert_eq!(
+            parse_key_arg("hex:5f00 01ff").unwrap(),
+            [0x5F, 0x00, 0x01, 0xFF]
+        );
+        assert_eq!(parse_key_arg("HEX:41").unwrap(), b"HEX:41");
+
+        assert_eq!(
+            parse_key_arg("hex:123"),
+            Err("Odd number of hex digits in 'hex:123'".to_string())
+        );
+        assert_eq!(
+            parse_key_arg("hex:zz"),
+            Err("Invalid hex digits in 'hex:zz'".to_string())
+        );
+        assert_eq!(
+            parse_key_arg("hex:+1"),
+            Err("Invalid hex digits in 'hex:+1'".to_string())
+        );
+        assert_eq!(
+            parse_key_arg("hex:äb"),
+            Err("Invalid hex digits in 'hex:äb'".to_string())
+        );
+    }
+
+    #[test]
+    fn state_names() {
+        assert_eq!(StateFilter::from_name("Live"), Some(StateFilter::Live));
+        assert_eq!(
+            StateFilter::from_name("deleted"),
+            Some(StateFilter::Deleted)
+        );
+        assert_eq!(StateFilter::from_name("all"), Some(StateFilter::All));
+        assert_eq!(StateFilter::from_name("gone"), None);
+    }
+}
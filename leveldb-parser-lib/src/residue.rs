@@ -0,0 +1,443 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::database;
+use crate::decoder_registry::{DecodeContext, DecoderRegistry};
+use crate::manifest_parser::{self, VersionState};
+use crate::verify;
+
+// status of the .ldb/.sst, .log and MANIFEST files of a database directory by
+// the AddFile / RemoveFile history of the current manifest, and the records
+// that only compaction residue (obsolete or unreferenced files) still holds
+// -----------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileStatus {
+    Live,         // in the current version
+    Obsolete,     // removed by the manifest (or older log / manifest), still present
+    Missing,      // in the current version, not present
+    Unreferenced, // present, never added by the manifest
+}
+
+pub struct FileEntry {
+    pub file_name: String,
+    pub file_no: u64,
+    pub status: FileStatus,
+    pub level: Option<u64>,     // live tables
+    pub file_size: Option<u64>, // size on disk
+}
+
+pub struct ResidueRecord {
+    pub status: FileStatus,
+    pub seq: u64,
+    pub state: u8,
+    pub key: String, // decoded
+    pub value: String,
+    pub kind: String,
+    pub file_path: String,
+    pub block_offset: u64, // batch offset in .log files
+    pub key_offset: u64,
+}
+
+pub struct ResidueReport {
+    pub dir_path: String,
+    pub manifest_name: String,
+    pub files: Vec<FileEntry>, // ordered by file number
+    pub records: Vec<ResidueRecord>,
+    pub errors: Vec<String>,
+}
+
+impl FileStatus {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FileStatus::Live => "live",
+            FileStatus::Obsolete => "obsolete",
+            FileStatus::Missing => "missing",
+            FileStatus::Unreferenced => "unreferenced",
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+pub fn analyze_dir(dir_path: &str) -> io::Result<ResidueReport> {
+    let dir = Path::new(dir_path);
+    let manifest_name = verify::read_current(dir)?;
+    let manifest = manifest_parser::parse_file(&dir.join(&manifest_name).to_string_lossy())?;
+    let version = manifest_parser::replay(&manifest);
+
+    let mut present = Vec::new();
+    for entry in fs::read_dir(dir)?.flatten() {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let file_size = entry
+            .metadata()
+            .ok()
+            .filter(|m| m.is_file())
+            .map(|m| m.len());
+        let Some(file_size) = file_size else {
+            continue;
+        };
+        let file_no = match file_name.strip_prefix("MANIFEST-") {
+            Some(number) => number.parse().ok(),
+            None => database::parse_file_no(&file_name),
+        };
+        if let Some(file_no) = file_no {
+            present.push((file_name, file_no, file_size));
+        }
+    }
+
+    let files = classify_files(&version, &manifest_name, &present);
+
+    let db = database::parse_dir(dir_path)?;
+    let statuses: HashMap<String, FileStatus> = files
+        .iter()
+        .map(|file| {
+            let path = dir.join(&file.file_name).to_string_lossy().to_string();
+            (path, file.status)
+        })
+        .collect();
+    let records = residue_records(&db, &statuses, &DecoderRegistry::default());
+
+    Ok(ResidueReport {
+        dir_path: dir_path.to_string(),
+        manifest_name,
+        files,
+        records,
+        errors: db.parse_errors,
+    })
+}
+
+// status of the numbered files of a directory (file name, number, size)
+//   tables: live / removed / never added by the manifest
+//   logs: LogNumber and PrevLogNumber are live, older logs were compacted
+//   manifests: the one of CURRENT is live
+pub fn classify_files(
+    version: &VersionState,
+    manifest_name: &str,
+    present: &[(String, u64, u64)],
+) -> Vec<FileEntry> {
+    let live_tables: BTreeMap<u64, u64> = version
+        .live_files
+        .iter()
+        .map(|table| (table.file_no, table.level))
+        .collect();
+    let added: HashSet<u64> = version.added_files.iter().copied().collect();
+    let removed: HashSet<u64> = version.removed_files.iter().copied().collect();
+    let log_number = version.log_number.unwrap_or(0);
+
+    let mut files = Vec::new();
+    for (file_name, file_no, file_size) in present {
+        let (status, level) = if file_name.ends_with(".ldb") || file_name.ends_with(".sst") {
+            match live_tables.get(file_no) {
+                Some(&level) => (FileStatus::Live, Some(level)),
+                None if added.contains(file_no) || removed.contains(file_no) => {
+                    (FileStatus::Obsolete, None)
+                }
+                None => (FileStatus::Unreferenced, None),
+            }
+        } else if file_name.ends_with(".log") {
+            if *file_no >= log_number || version.prev_log_number == Some(*file_no) {
+                (FileStatus::Live, None)
+            } else {
+                (FileStatus::Obsolete, None)
+            }
+        } else if file_name.starts_with("MANIFEST-") {
+            if file_name == manifest_name {
+                (FileStatus::Live, None)
+            } else {
+                (FileStatus::Obsolete, None)
+            }
+        } else {
+            continue;
+        };
+
+        files.push(FileEntry {
+            file_name: file_name.clone(),
+            file_no: *file_no,
+            status,
+            level,
+            file_size: Some(*file_size),
+        });
+    }
+
+    // live tables and the current log without a file
+    for table in &version.live_files {
+        if !files.iter().any(|file| file.file_no == table.file_no) {
+            files.push(FileEntry {
+                file_name: format!("{:06}.ldb", table.file_no),
+                file_no: table.file_no,
+                status: FileStatus::Missing,
+                level: Some(table.level),
+                file_size: None,
+            });
+        }
+    }
+    if let Some(log_number) = version.log_number
+        && !files.iter().any(|file| file.file_no == log_number)
+    {
+        files.push(FileEntry {
+            file_name: format!("{:06}.log", log_number),
+            file_no: log_number,
+            status: FileStatus::Missing,
+            level: None,
+            file_size: None,
+        });
+    }
+
+    files.sort_by(|a, b| {
+        a.file_no
+            .cmp(&b.file_no)
+            .then_with(|| a.file_name.cmp(&b.file_name))
+    });
+    files
+}
+
+// records of obsolete and unreferenced files without the same (key, seq,
+// state) in a live file, ordered by sequence number
+pub fn residue_records(
+    db: &database::Database,
+    statuses: &HashMap<String, FileStatus>,
+    registry: &DecoderRegistry,
+) -> Vec<ResidueRecord> {
+    let records = database::collect_records(db);
+    let status_of = |file_path: &str| {
+        statuses
+            .get(file_path)
+            .copied()
+            .unwrap_or(FileStatus::Unreferenced)
+    };
+
+    let live: HashSet<(&[u8], u64, u8)> = records
+        .iter()
+        .filter(|record| status_of(&record.file_path) == FileStatus::Live)
+        .map(|record| (record.key.as_slice(), record.seq, record.state))
+        .collect();
+
+    let mut residue = Vec::new();
+    let mut seen = HashSet::new();
+    for record in &records {
+        let status = status_of(&record.file_path);
+        let id = (record.key.as_slice(), record.seq, record.state);
+        // the same record may be in several obsolete files (e.g. a .log and
+        // the table it was compacted into)
+        if status == FileStatus::Live || live.contains(&id) || !seen.insert(id) {
+            continue;
+        }
+
+        let (key, value, kind) = registry.decode_kv(&DecodeContext {
+            path: &record.file_path,
+            storage_kind: db.storage_kind,
+            key: &record.key,
+            value: record.value.as_deref(),
+        });
+        residue.push(ResidueRecord {
+            status,
+            seq: record.seq,
+            state: record.state,
+            key,
+            value,
+            kind,
+            file_path: record.file_path.clone(),
+            block_offset: record.block_offset,
+            key_offset: record.key_offset,
+        });
+    }
+
+    residue
+}
+
+// -----------------------------------------------------------------------------
+pub mod display {
+    use super::*;
+
+    pub fn print_report(report: &ResidueReport) -> io::Result<()> {
+        writeln!(io::stdout(), "Database: {}", report.dir_path)?;
+        writeln!(io::stdout(), "Manifest: {}", report.manifest_name)?;
+        for file in &report.files {
+            let level = file
+                .level
+                .map(|level| format!(", level {}", level))
+                .unwrap_or_default();
+            let size = file
+                .file_size
+                .map(|size| format!(", {} bytes", size))
+                .unwrap_or_default();
+            writeln!(
+                io::stdout(),
+                "{:<13} {}{}{}",
+                file.status.name(),
+                file.file_name,
+                level,
+                size
+            )?;
+        }
+        writeln!(
+            io::stdout(),
+            "Records only in obsolete / unreferenced files: {}",
+            report.records.len()
+        )?;
+        for record in &report.records {
+            let state_str = match record.state {
+                0 => "deleted",
+                1 => "live",
+                _ => "unknown",
+            };
+            writeln!(
+                io::stdout(),
+                "  seq {} ({}) {} => {} [{}]",
+                record.seq,
+                state_str,
+                record.key,
+                record.value,
+                record.file_path
+            )?;
+        }
+        Ok(())
+    }
+}
+
+pub mod export {
+    use super::*;
+
+    pub fn files_csv_string(report: &ResidueReport) -> String {
+        let mut csv = String::new();
+        // Header
+        csv.push_str("\"Status\",\"File\",\"No\",\"Level\",\"Size\",\"DB\"\n");
+
+        for file in &report.files {
+            csv.push_str(&format!(
+                "\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\"\n",
+                file.status.name(),
+                file.file_name.replace("\"", "\"\""),
+                file.file_no,
+                file.level
+                    .map(|level| level.to_string())
+                    .unwrap_or_default(),
+                file.file_size
+                    .map(|size| size.to_string())
+                    .unwrap_or_default(),
+                report.dir_path.replace("\"", "\"\""),
+            ));
+        }
+
+        csv
+    }
+
+    pub fn records_csv_string(report: &ResidueReport) -> String {
+        let mut csv = String::new();
+        // Header
+        csv.push_str("\"FileSt\",\"Seq\",\"St\",\"K\",\"V\",\"Kind\",\"BO\",\"KO\",\"FP\"\n");
+
+        for record in &report.records {
+            let state_str = match record.state {
+                0 => "deleted",
+                1 => "live",
+                _ => "unknown",
+            };
+
+            csv.push_str(&format!(
+                "\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\"\n",
+                record.status.name(),
+                record.seq,
+                state_str,
+                record.key.replace("\"", "\"\""),
+                record.value.replace("\"", "\"\""),
+                record.kind,
+                record.block_offset,
+                record.key_offset,
+                record.file_path.replace("\"", "\"\""),
+            ));
+        }
+
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest_parser::TableFile;
+
+    fn table(level: u64, file_no: u64) -> TableFile {
+        TableFile {
+            level,
+            file_no,
+            file_size: 100,
+            sm_key: Vec::new(),
+            sm_seq: 0,
+            sm_state: 1,
+            lg_key: Vec::new(),
+            lg_seq: 0,
+            lg_state: 1,
+        }
+    }
+
+    fn present(names: &[&str]) -> Vec<(String, u64, u64)> {
+        names
+            .iter()
+            .map(|name| {
+                let digits: String = name.chars().filter(char::is_ascii_digit).collect();
+                (name.to_string(), digits.parse().unwrap_or(0), 10)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn classify_files_by_manifest_history() {
+        let version = VersionState {
+            log_number: Some(7),
+            live_files: vec![table(1, 6), table(2, 8)],
+            added_files: vec![4, 6, 8],
+            removed_files: vec![4],
+            ..Default::default()
+        };
+        let files = classify_files(
+            &version,
+            "MANIFEST-000002",
+            &present(&[
+                "MANIFEST-000001",
+                "MANIFEST-000002",
+                "000001.log",
+                "000003.ldb",
+                "000004.ldb",
+                "000006.ldb",
+                "000007.log",
+                "LOG",
+            ]),
+        );
+
+        let statuses: Vec<(&str, FileStatus, Option<u64>)> = files
+            .iter()
+            .map(|file| (file.file_name.as_str(), file.status, file.level))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ("000001.log", FileStatus::Obsolete, None),
+                ("MANIFEST-000001", FileStatus::Obsolete, None),
+                ("MANIFEST-000002", FileStatus::Live, None),
+                ("000003.ldb", FileStatus::Unreferenced, None),
+                ("000004.ldb", FileStatus::Obsolete, None),
+                ("000006.ldb", FileStatus::Live, Some(1)),
+                ("000007.log", FileStatus::Live, None),
+                ("000008.ldb", FileStatus::Missing, Some(2)),
+            ]
+        );
+        assert_eq!(files.last().unwrap().file_size, None);
+    }
+
+    #[test]
+    fn classify_files_previous_and_missing_log() {
+        let version = VersionState {
+            log_number: Some(9),
+            prev_log_number: Some(5),
+            ..Default::default()
+        };
+        let files = classify_files(&version, "MANIFEST-000001", &present(&["000005.log"]));
+
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].status, FileStatus::Live);
+        assert_eq!(files[1].file_name, "000009.log");
+        assert_eq!(files[1].status, FileStatus::Missing);
+    }
+}
@@ -0,0 +1,509 @@
+use regex::{Regex, RegexBuilder, bytes};
+
+use crate::database::{self, Database, DbRecord};
+use crate::decoder::{self, StorageKind};
+use crate::decoder_registry::{DecodeContext, DecoderRegistry};
+
+// full-text search over the raw keys / values (UTF-8, UTF-16LE/BE and
+// Latin-1 encodings of the needle) and the decoded strings of decode_kv
+//   case-insensitive matching on raw bytes folds ASCII letters only, the
+//   decoded strings are folded fully
+// -----------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchMode {
+    Literal,
+    CaseInsensitive,
+    Regex,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Latin1,
+    Decoded, // decoded key / value string
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Field {
+    Key,
+    Value,
+}
+
+pub struct SearchQuery {
+    pub pattern: String,
+    pub mode: SearchMode,
+    raw: Vec<(Encoding, bytes::Regex)>, // byte patterns of the needle encodings
+    text: Regex,                        // decoded strings
+}
+
+pub struct SearchHit {
+    pub file_path: String,
+    pub seq: u64,
+    pub state: u8,
+    pub block_offset: u64,
+    pub key_offset: u64,
+    pub field: Field,
+    pub encoding: Encoding,
+    pub offset: Option<usize>, // in the raw key / value, None for decoded matches
+    pub matched: String,
+    pub key: String, // decoded
+    pub value: String,
+    pub kind: String,
+}
+
+// raw match: encoding, byte offset, matched text
+pub struct ByteMatch {
+    pub encoding: Encoding,
+    pub offset: usize,
+    pub matched: String,
+}
+
+impl Encoding {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf16Le => "UTF-16LE",
+            Encoding::Utf16Be => "UTF-16BE",
+            Encoding::Latin1 => "Latin-1",
+            Encoding::Decoded => "decoded",
+        }
+    }
+}
+
+impl SearchMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "literal" => Some(SearchMode::Literal),
+            "icase" | "case-insensitive" => Some(SearchMode::CaseInsensitive),
+            "regex" => Some(SearchMode::Regex),
+            _ => None,
+        }
+    }
+}
+
+impl SearchQuery {
+    pub fn new(pattern: &str, mode: SearchMode) -> Result<Self, String> {
+        if pattern.is_empty() {
+            return Err("Empty search pattern".to_string());
+        }
+
+        let case_insensitive = mode == SearchMode::CaseInsensitive;
+        let mut raw = Vec::new();
+
+        let text = match mode {
+            SearchMode::Regex => {
+                raw.push((Encoding::Utf8, build_bytes_regex(pattern)?));
+                build_regex(pattern, false)?
+            }
+            SearchMode::Literal | SearchMode::CaseInsensitive => {
+                let utf16: Vec<u16> = pattern.encode_utf16().collect();
+                let mut encodings = vec![
+                    (Encoding::Utf8, pattern.as_bytes().to_vec()),
+                    (
+                        Encoding::Utf16Le,
+                        utf16.iter().flat_map(|unit| unit.to_le_bytes()).collect(),
+                    ),
+                    (
+                        Encoding::Utf16Be,
+                        utf16.iter().flat_map(|unit| unit.to_be_bytes()).collect(),
+                    ),
+                ];
+                // Latin-1 differs from UTF-8 only for non-ASCII needles
+                if !pattern.is_ascii() && pattern.chars().all(|c| (c as u32) <= 0xFF) {
+                    encodings.push((Encoding::Latin1, pattern.chars().map(|c| c as u8).collect()));
+                }
+
+                for (encoding, needle) in encodings {
+                    raw.push((
+                        encoding,
+                        build_bytes_regex(&escape_bytes(&needle, case_insensitive))?,
+                    ));
+                }
+                build_regex(&regex::escape(pattern), case_insensitive)?
+            }
+        };
+
+        Ok(SearchQuery {
+            pattern: pattern.to_string(),
+            mode,
+            raw,
+            text,
+        })
+    }
+
+    pub fn is_match_str(&self, s: &str) -> bool {
+        self.text.is_match(s)
+    }
+}
+
+// -----------------------------------------------------------------------------
+// matches in raw bytes, a match overlapping an earlier one (e.g. UTF-16LE
+// text read as UTF-16BE one byte off) is dropped
+pub fn search_bytes(query: &SearchQuery, data: &[u8]) -> Vec<ByteMatch> {
+    let mut matches = Vec::new();
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    let mut accept = |start: usize, end: usize| {
+        let overlaps = ranges.iter().any(|&(s, e)| start < e && s < end);
+        if !overlaps {
+            ranges.push((start, end));
+        }
+        !overlaps
+    };
+
+    for (encoding, regex) in &query.raw {
+        for m in regex.find_iter(data) {
+            if accept(m.start(), m.end()) {
+                matches.push(ByteMatch {
+                    encoding: *encoding,
+                    offset: m.start(),
+                    matched: decode_match(*encoding, m.as_bytes()),
+                });
+            }
+        }
+    }
+
+    // regex patterns are text, UTF-16 and Latin-1 data is decoded first
+    if query.mode == SearchMode::Regex {
+        for (encoding, offset, len, matched) in search_decoded_bytes(&query.text, data) {
+            if accept(offset, offset + len) {
+                matches.push(ByteMatch {
+                    encoding,
+                    offset,
+                    matched,
+                });
+            }
+        }
+    }
+
+    matches.sort_by_key(|m| m.offset);
+    matches
+}
+
+// raw matches of key and value, decoded strings only if the raw field did not match
+pub fn search_records(
+    records: &[DbRecord],
+    storage_kind: StorageKind,
+    registry: &DecoderRegistry,
+    query: &SearchQuery,
+) -> Vec<SearchHit> {
+    let mut hits = Vec::new();
+
+    for record in records {
+        let ctx = DecodeContext {
+            path: &record.file_path,
+            storage_kind,
+            key: &record.key,
+            value: record.value.as_deref(),
+        };
+        let decoded = registry.decode_kv(&ctx);
+        hits.extend(search_record(record, &decoded, query));
+    }
+
+    hits
+}
+
+// matches of one record with its decoded (key, value, kind) strings
+pub fn search_record(
+    record: &DbRecord,
+    decoded: &(String, String, String),
+    query: &SearchQuery,
+) -> Vec<SearchHit> {
+    let (key_str, value_str, kind_str) = decoded;
+    let mut hits = Vec::new();
+
+    let fields = [
+        (Field::Key, record.key.as_slice(), key_str),
+        (
+            Field::Value,
+            record.value.as_deref().unwrap_or(&[]),
+            value_str,
+        ),
+    ];
+    for (field, raw, decoded) in fields {
+        let mut field_hits: Vec<(Encoding, Option<usize>, String)> = search_bytes(query, raw)
+            .into_iter()
+            .map(|m| (m.encoding, Some(m.offset), m.matched))
+            .collect();
+        if field_hits.is_empty() {
+            field_hits = query
+                .text
+                .find_iter(decoded)
+                .map(|m| (Encoding::Decoded, None, m.as_str().to_string()))
+                .collect();
+        }
+
+        for (encoding, offset, matched) in field_hits {
+            hits.push(SearchHit {
+                file_path: record.file_path.clone(),
+                seq: record.seq,
+                state: record.state,
+                block_offset: record.block_offset,
+                key_offset: record.key_offset,
+                field,
+                encoding,
+                offset,
+                matched,
+                key: key_str.clone(),
+                value: value_str.clone(),
+                kind: kind_str.clone(),
+            });
+        }
+    }
+
+    hits
+}
+
+// all .log and .ldb records of a parsed database directory
+pub fn search_database(db: &Database, query: &SearchQuery) -> Vec<SearchHit> {
+    search_records(
+        &database::collect_records(db),
+        db.storage_kind,
+        &DecoderRegistry::default(),
+        query,
+    )
+}
+
+// -----------------------------------------------------------------------------
+fn build_regex(pattern: &str, case_insensitive: bool) -> Result<Regex, String> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(case_insensitive)
+        .build()
+        .map_err(|e| format!("Invalid regex '{}': {}", pattern, e))
+}
+
+fn build_bytes_regex(pattern: &str) -> Result<bytes::Regex, String> {
+    bytes::Regex::new(pattern).map_err(|e| format!("Invalid regex '{}': {}", pattern, e))
+}
+
+// byte pattern without Unicode, (?i) then folds ASCII letters only
+fn escape_bytes(needle: &[u8], case_insensitive: bool) -> String {
+    let flags = if case_insensitive { "(?i-u)" } else { "(?-u)" };
+    let escaped: String = needle.iter().map(|b| format!("\\x{:02X}", b)).collect();
+    format!("{}{}", flags, escaped)
+}
+
+fn decode_match(encoding: Encoding, bytes: &[u8]) -> String {
+    match encoding {
+        Encoding::Utf16Le => decode_utf16(bytes, u16::from_le_bytes),
+        Encoding::Utf16Be => decode_utf16(bytes, u16::from_be_bytes),
+        Encoding::Latin1 => bytes.iter().map(|&b| b as char).collect(),
+        Encoding::Utf8 | Encoding::Decoded => decoder::bytes_to_utf8_lossy(bytes),
+    }
+}
+
+fn decode_utf16(bytes: &[u8], from_bytes: fn([u8; 2]) -> u16) -> String {
+    let units = bytes
+        .chunks_exact(2)
+        .map(|pair| from_bytes([pair[0], pair[1]]));
+    char::decode_utf16(units)
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
+
+// regex matches on the UTF-16LE/BE (both alignments) and Latin-1 decoding
+// of the bytes, with the byte offset and length of each match
+fn search_decoded_bytes(regex: &Regex, data: &[u8]) -> Vec<(Encoding, usize, usize, String)> {
+    let mut matches = Vec::new();
+
+    // UTF-16 text of common scripts has zero bytes, other data decodes to noise
+    let utf16_alignments = if data.contains(&0) { 0..2 } else { 0..0 };
+    for (encoding, from_bytes) in [
+        (Encoding::Utf16Le, u16::from_le_bytes as fn([u8; 2]) -> u16),
+        (Encoding::Utf16Be, u16::from_be_bytes),
+    ] {
+        for alignment in utf16_alignments.clone() {
+            let aligned = data.get(alignment..).unwrap_or(&[]);
+            // every unpaired surrogate becomes one replacement character,
+            // so the UTF-16 length of a prefix is its length in code units
+            let text = decode_utf16(aligned, from_bytes);
+            for m in regex.find_iter(&text) {
+                let units = text[..m.start()].encode_utf16().count();
+                let len = 2 * m.as_str().encode_utf16().count();
+                matches.push((encoding, alignment + 2 * units, len, m.as_str().to_string()));
+            }
+        }
+    }
+
+    // only non-ASCII data differs from the UTF-8 matches
+    if !data.is_ascii() {
+        let text: String = data.iter().map(|&b| b as char).collect();
+        for m in regex.find_iter(&text) {
+            let offset = text[..m.start()].chars().count();
+            let len = m.as_str().chars().count();
+            matches.push((Encoding::Latin1, offset, len, m.as_str().to_string()));
+        }
+    }
+
+    matches
+}
+
+// -----------------------------------------------------------------------------
+pub mod export {
+    use super::*;
+
+    pub fn csv_string(hits: &[SearchHit]) -> String {
+        let mut csv = String::new();
+        // Header
+        csv.push_str(
+            "\"Seq\",\"St\",\"Field\",\"Encoding\",\"Offset\",\"Match\",\"K\",\"V\",\"Kind\",\"BO\",\"KO\",\"FP\"\n",
+        );
+
+        for hit in hits {
+            let state_str = match hit.state {
+                0 => "deleted",
+                1 => "live",
+                _ => "unknown",
+            };
+            let field_str = match hit.field {
+                Field::Key => "key",
+                Field::Value => "value",
+            };
+
+            csv.push_str(&format!(
+                "\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\"\n",
+                hit.seq,
+                state_str,
+                field_str,
+                hit.encoding.name(),
+                hit.offset.map(|offset| offset.to_string()).unwrap_or_default(),
+                hit.matched.replace("\"", "\"\""),
+                hit.key.replace("\"", "\"\""),
+                hit.value.replace("\"", "\"\""),
+                hit.kind,
+                hit.block_offset,
+                hit.key_offset,
+                hit.file_path.replace("\"", "\"\""),
+            ));
+        }
+
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16le(s: &str) -> Vec<u8> {
+        s.encode_utf16()
+            .flat_map(|unit| unit.to_le_bytes())
+            .collect()
+    }
+
+    fn record(key: &[u8], value: &[u8]) -> DbRecord {
+        DbRecord {
+            seq: 7,
+            state: 1,
+            key: key.to_vec(),
+            value: Some(value.to_vec()),
+            file_path: "000003.log".to_string(),
+            block_offset: 0,
+            key_offset: 0,
+        }
+    }
+
+    fn decoded(key: &str, value: &str) -> (String, String, String) {
+        (key.to_string(), value.to_string(), "G".to_string())
+    }
+
+    #[test]
+    fn utf16le_needle_in_value() {
+        let query = SearchQuery::new("secret", SearchMode::Literal).unwrap();
+        let mut value = vec![0x00];
+        value.extend(utf16le("my secret token"));
+        let record = record(b"_https://a\x00\x01k", &value);
+
+        let hits = search_record(&record, &decoded("k", "my secret token"), &query);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].field, Field::Value);
+        assert_eq!(hits[0].encoding, Encoding::Utf16Le);
+        assert_eq!(hits[0].matched, "secret");
+        // flag byte + "my " in UTF-16LE
+        assert_eq!(hits[0].offset, Some(1 + 2 * 3));
+    }
+
+    #[test]
+    fn raw_hit_offsets() {
+        let query = SearchQuery::new("ab", SearchMode::Literal).unwrap();
+        let matches = search_bytes(&query, b"xxabyyab");
+        let found: Vec<(Encoding, usize)> =
+            matches.iter().map(|m| (m.encoding, m.offset)).collect();
+        assert_eq!(found, vec![(Encoding::Utf8, 2), (Encoding::Utf8, 6)]);
+
+        // Latin-1 encoding of a non-ASCII needle
+        let query = SearchQuery::new("café", SearchMode::Literal).unwrap();
+        let matches = search_bytes(&query, b"le caf\xE9");
+        assert_eq!(matches.len(), 1);
+        assert_eq!(
+            (
+                matches[0].encoding,
+                matches[0].offset,
+                matches[0].matched.as_str()
+            ),
+            (Encoding::Latin1, 3, "café")
+        );
+    }
+
+    #[test]
+    fn case_insensitive_regex() {
+        let query = SearchQuery::new("(?i)tok[a-z]+", SearchMode::Regex).unwrap();
+
+        let matches = search_bytes(&query, b"id=TOKEN1");
+        assert_eq!(matches.len(), 1);
+        assert_eq!(
+            (matches[0].offset, matches[0].matched.as_str()),
+            (3, "TOKEN")
+        );
+
+        // regex on UTF-16LE data, offset in bytes
+        let mut data = vec![0x01];
+        data.extend(utf16le("a Token"));
+        let matches = search_bytes(&query, &data);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(
+            (
+                matches[0].encoding,
+                matches[0].offset,
+                matches[0].matched.as_str()
+            ),
+            (Encoding::Utf16Le, 1 + 2 * 2, "Token")
+        );
+
+        assert!(query.is_match_str("TOKEN"));
+        assert!(SearchQuery::new("(", SearchMode::Regex).is_err());
+    }
+
+    #[test]
+    fn case_insensitive_literal() {
+        let query = SearchQuery::new("Secret", SearchMode::CaseInsensitive).unwrap();
+        let matches = search_bytes(&query, &utf16le("SECRET"));
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].encoding, Encoding::Utf16Le);
+        assert_eq!(matches[0].matched, "SECRET");
+
+        let query = SearchQuery::new("Secret", SearchMode::Literal).unwrap();
+        assert!(search_bytes(&query, b"SECRET").is_empty());
+    }
+
+    #[test]
+    fn decoded_string_only_without_raw_hit() {
+        let query = SearchQuery::new("2023", SearchMode::Literal).unwrap();
+        // timestamp only visible in the decoded value
+        let varint_record = record(b"k", &[0x08, 0x80, 0x80, 0x80]);
+        let hits = search_record(
+            &varint_record,
+            &decoded("k", "2023-11-14T22:13:20Z"),
+            &query,
+        );
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].encoding, Encoding::Decoded);
+        assert_eq!(hits[0].offset, None);
+
+        let text_record = record(b"k", b"2023");
+        let hits = search_record(&text_record, &decoded("k", "2023"), &query);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].encoding, Encoding::Utf8);
+    }
+}
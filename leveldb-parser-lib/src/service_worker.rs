@@ -0,0 +1,461 @@
+use std::collections::BTreeMap;
+
+use crate::database::DbRecord;
+use crate::decoder;
+use crate::protobuf::{self, ProtoField, ProtoValue};
+
+// Service Worker/Database keys (see Chromium service_worker_database.cc):
+//   INITDATA_NEXT_REGISTRATION_ID      => next id (ASCII), same for
+//   INITDATA_NEXT_RESOURCE_ID / INITDATA_NEXT_VERSION_ID
+//   INITDATA_UNIQUE_ORIGIN:<origin>    => ""
+//   REG:<origin>\x00<registration id>  => ServiceWorkerRegistrationData
+//   REGID_TO_ORIGIN:<registration id>  => origin
+//   RES:<version id>\x00<resource id>  => ServiceWorkerResourceRecord
+//   REG_USER_DATA:<registration id>\x00<user data key> => user data
+// -----------------------------------------------------------------------------
+const KEY_SEPARATOR: u8 = 0x00;
+
+pub struct ServiceWorkerDb {
+    pub next_registration_id: Option<u64>,
+    pub next_resource_id: Option<u64>,
+    pub next_version_id: Option<u64>,
+    pub origins: Vec<String>,
+    pub registrations: Vec<Registration>,
+}
+
+pub struct Registration {
+    pub origin: String,
+    pub registration_id: u64,
+    pub scope: Option<String>,
+    pub script_url: Option<String>,
+    pub version_id: Option<u64>,
+    pub is_active: Option<bool>,
+    pub has_fetch_handler: Option<bool>,
+    pub last_update_check_time: Option<i64>,
+    pub resources_total_size: Option<u64>,
+    pub resources: Vec<Resource>,
+    pub user_data: Vec<(String, String)>,
+    pub seq: u64,
+    pub state: u8,
+    pub file_path: String,
+}
+
+pub struct Resource {
+    pub version_id: u64,
+    pub resource_id: u64,
+    pub url: Option<String>,
+    pub size: Option<u64>,
+    pub seq: u64,
+    pub state: u8,
+}
+
+// -----------------------------------------------------------------------------
+// latest version of every key wins, deleted keys keep their last known data
+pub fn build(records: &[DbRecord]) -> ServiceWorkerDb {
+    let mut next_registration_id = None;
+    let mut next_resource_id = None;
+    let mut next_version_id = None;
+    let mut origins = Vec::new();
+    let mut registrations: BTreeMap<(String, u64), Registration> = BTreeMap::new();
+    let mut resources: BTreeMap<(u64, u64), Resource> = BTreeMap::new();
+    let mut user_data: BTreeMap<(u64, String), (u64, Option<String>)> = BTreeMap::new();
+
+    let mut sorted: Vec<&DbRecord> = records.iter().collect();
+    sorted.sort_by_key(|record| record.seq);
+
+    for record in sorted {
+        let key = record.key.as_slice();
+        let value = record.value.as_deref();
+
+        if key == b"INITDATA_NEXT_REGISTRATION_ID" {
+            next_registration_id = value.and_then(parse_ascii_u64).or(next_registration_id);
+        } else if key == b"INITDATA_NEXT_RESOURCE_ID" {
+            next_resource_id = value.and_then(parse_ascii_u64).or(next_resource_id);
+        } else if key == b"INITDATA_NEXT_VERSION_ID" {
+            next_version_id = value.and_then(parse_ascii_u64).or(next_version_id);
+        } else if let Some(origin) = key.strip_prefix(b"INITDATA_UNIQUE_ORIGIN:") {
+            let origin = decoder::bytes_to_utf8_lossy(origin);
+            if !origins.contains(&origin) {
+                origins.push(origin);
+            }
+        } else if let Some(rest) = key.strip_prefix(b"REG:") {
+            let Some((origin, registration_id)) = split_key(rest) else {
+                continue;
+            };
+            let Some(registration_id) = parse_ascii_u64(registration_id) else {
+                continue;
+            };
+            let origin = decoder::bytes_to_utf8_lossy(origin);
+
+            let registration = registrations
+                .entry((origin.clone(), registration_id))
+                .or_insert_with(|| Registration {
+                    origin,
+                    registration_id,
+                    scope: None,
+                    script_url: None,
+                    version_id: None,
+                    is_active: None,
+                    has_fetch_handler: None,
+                    last_update_check_time: None,
+                    resources_total_size: None,
+                    resources: Vec::new(),
+                    user_data: Vec::new(),
+                    seq: 0,
+                    state: 0,
+                    file_path: String::new(),
+                });
+            registration.seq = record.seq;
+            registration.state = record.state;
+            registration.file_path = record.file_path.clone();
+
+            if let Some(fields) = value.and_then(protobuf::decode_message) {
+                // ServiceWorkerRegistrationData
+                registration.scope = string_field(&fields, 2);
+                registration.script_url = string_field(&fields, 3);
+                registration.version_id = varint_field(&fields, 4);
+                registration.is_active = varint_field(&fields, 5).map(|v| v != 0);
+                registration.has_fetch_handler = varint_field(&fields, 6).map(|v| v != 0);
+                registration.last_update_check_time = varint_field(&fields, 7).map(|v| v as i64);
+                registration.resources_total_size = varint_field(&fields, 8);
+            }
+        } else if let Some(rest) = key.strip_prefix(b"RES:") {
+            let Some((version_id, resource_id)) = split_key(rest) else {
+                continue;
+            };
+            let (Some(version_id), Some(resource_id)) =
+                (parse_ascii_u64(version_id), parse_ascii_u64(resource_id))
+            else {
+                continue;
+            };
+
+            let resource = resources
+                .entry((version_id, resource_id))
+                .or_insert_with(|| Resource {
+                    version_id,
+                    resource_id,
+                    url: None,
+                    size: None,
+                    seq: 0,
+                    state: 0,
+                });
+            resource.seq = record.seq;
+            resource.state = record.state;
+
+            if let Some(fields) = value.and_then(protobuf::decode_message) {
+                // ServiceWorkerResourceRecord
+                resource.url = string_field(&fields, 2);
+                resource.size = varint_field(&fields, 3);
+            }
+        } else if let Some(rest) = key.strip_prefix(b"REG_USER_DATA:") {
+            let Some((registration_id, data_key)) = split_key(rest) else {
+                continue;
+            };
+            let Some(registration_id) = parse_ascii_u64(registration_id) else {
+                continue;
+            };
+            user_data.insert(
+                (registration_id, decoder::bytes_to_utf8_lossy(data_key)),
+                (record.seq, value.map(decoder::bytes_to_utf8_lossy)),
+            );
+        }
+    }
+
+    let mut registrations: Vec<Registration> = registrations.into_values().collect();
+    for registration in &mut registrations {
+        if let Some(version_id) = registration.version_id {
+            registration.resources = resources
+                .range((version_id, 0)..=(version_id, u64::MAX))
+                .map(|(_, resource)| Resource {
+                    version_id: resource.version_id,
+                    resource_id: resource.resource_id,
+                    url: resource.url.clone(),
+                    size: resource.size,
+                    seq: resource.seq,
+                    state: resource.state,
+                })
+                .collect();
+        }
+        registration.user_data = user_data
+            .iter()
+            .filter(|((id, _), _)| *id == registration.registration_id)
+            .filter_map(|((_, data_key), (_, data))| {
+                data.as_ref().map(|data| (data_key.clone(), data.clone()))
+            })
+            .collect();
+    }
+
+    ServiceWorkerDb {
+        next_registration_id,
+        next_resource_id,
+        next_version_id,
+        origins,
+        registrations,
+    }
+}
+
+fn split_key(bytes: &[u8]) -> Option<(&[u8], &[u8])> {
+    let pos = bytes.iter().position(|&b| b == KEY_SEPARATOR)?;
+    Some((&bytes[..pos], &bytes[pos + 1..]))
+}
+
+fn parse_ascii_u64(bytes: &[u8]) -> Option<u64> {
+    std::str::from_utf8(bytes).ok()?.trim().parse().ok()
+}
+
+fn string_field(fields: &[ProtoField], field_number: u64) -> Option<String> {
+    match protobuf::find_field(fields, field_number)? {
+        ProtoValue::String(s) => Some(s.clone()),
+        ProtoValue::Bytes(bytes) => Some(decoder::bytes_to_utf8_lossy(bytes)),
+        _ => None,
+    }
+}
+
+fn varint_field(fields: &[ProtoField], field_number: u64) -> Option<u64> {
+    match protobuf::find_field(fields, field_number)? {
+        ProtoValue::Varint(v) => Some(*v),
+        _ => None,
+    }
+}
+
+// -----------------------------------------------------------------------------
+pub mod export {
+    use super::*;
+
+    pub fn csv_string(db: &ServiceWorkerDb) -> String {
+        let mut csv = String::new();
+        // Header
+        csv.push_str(
+            "\"Seq\",\"Origin\",\"RegId\",\"Scope\",\"Script\",\"VersionId\",\"Active\",\"LastUpdateCheck\",\"Resources\",\"St\",\"FP\"\n",
+        );
+
+        for registration in &db.registrations {
+            let state_str = match registration.state {
+                0 => "deleted",
+                1 => "live",
+                _ => "unknown",
+            };
+
+            let resources: Vec<String> = registration
+                .resources
+                .iter()
+                .map(|resource| {
+                    format!(
+                        "{} ({} Bytes)",
+                        resource.url.as_deref().unwrap_or(""),
+                        resource.size.unwrap_or(0)
+                    )
+                })
+                .collect();
+
+            csv.push_str(&format!(
+                "\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\"\n",
+                registration.seq,
+                registration.origin.replace("\"", "\"\""),
+                registration.registration_id,
+                registration
+                    .scope
+                    .as_deref()
+                    .unwrap_or("")
+                    .replace("\"", "\"\""),
+                registration
+                    .script_url
+                    .as_deref()
+                    .unwrap_or("")
+                    .replace("\"", "\"\""),
+                registration
+                    .version_id
+                    .map(|v| v.to_string())
+                    .unwrap_or_default(),
+                registration
+                    .is_active
+                    .map(|v| v.to_string())
+                    .unwrap_or_default(),
+                registration
+                    .last_update_check_time
+                    .map(decoder::format_chrome_timestamp)
+                    .unwrap_or_default(),
+                resources.join("; ").replace("\"", "\"\""),
+                state_str,
+                registration.file_path,
+            ));
+        }
+
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(key: &[u8], seq: u64, value: Option<&[u8]>) -> DbRecord {
+        DbRecord {
+            seq,
+            state: value.is_some() as u8,
+            key: key.to_vec(),
+            value: value.map(|value| value.to_vec()),
+            file_path: "000003.log".to_string(),
+            block_offset: 0,
+            key_offset: 0,
+        }
+    }
+
+    fn varint(mut value: u64) -> Vec<u8> {
+        let mut out = Vec::new();
+        while value >= 0x80 {
+            out.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        out.push(value as u8);
+        out
+    }
+
+    fn encode_varint_field(field_number: u64, value: u64) -> Vec<u8> {
+        [varint(field_number << 3), varint(value)].concat()
+    }
+
+    fn encode_string_field(field_number: u64, s: &str) -> Vec<u8> {
+        [
+            varint((field_number << 3) | 2),
+            varint(s.len() as u64),
+            s.as_bytes().to_vec(),
+        ]
+        .concat()
+    }
+
+    // ServiceWorkerRegistrationData
+    fn registration_data(version_id: u64) -> Vec<u8> {
+        [
+            encode_varint_field(1, 5),
+            encode_string_field(2, "https://example.com/"),
+            encode_string_field(3, "https://example.com/sw.js"),
+            encode_varint_field(4, version_id),
+            encode_varint_field(5, 1),
+            encode_varint_field(6, 0),
+            encode_varint_field(7, 13_345_678_901_234_567),
+            encode_varint_field(8, 2048),
+        ]
+        .concat()
+    }
+
+    // ServiceWorkerResourceRecord
+    fn resource_record(resource_id: u64, url: &str, size: u64) -> Vec<u8> {
+        [
+            encode_varint_field(1, resource_id),
+            encode_string_field(2, url),
+            encode_varint_field(3, size),
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn registration_with_resources_and_user_data() {
+        let records = [
+            record(b"INITDATA_NEXT_REGISTRATION_ID", 1, Some(b"6")),
+            record(b"INITDATA_NEXT_VERSION_ID", 2, Some(b"9")),
+            record(b"INITDATA_UNIQUE_ORIGIN:https://example.com/", 3, Some(b"")),
+            record(
+                b"REG:https://example.com/\x005",
+                4,
+                Some(&registration_data(8)),
+            ),
+            record(
+                b"RES:8\x0010",
+                5,
+                Some(&resource_record(10, "https://example.com/sw.js", 1500)),
+            ),
+            record(
+                b"RES:8\x0011",
+                6,
+                Some(&resource_record(11, "https://example.com/lib.js", 548)),
+            ),
+            // resource of an older version
+            record(
+                b"RES:7\x003",
+                7,
+                Some(&resource_record(3, "https://example.com/old.js", 1)),
+            ),
+            record(b"REG_USER_DATA:5\x00push_token", 8, Some(b"abc")),
+            record(b"REG_USER_DATA:5\x00removed", 9, None),
+        ];
+
+        let db = build(&records);
+        assert_eq!(db.next_registration_id, Some(6));
+        assert_eq!(db.next_version_id, Some(9));
+        assert_eq!(db.next_resource_id, None);
+        assert_eq!(db.origins, vec!["https://example.com/"]);
+
+        let registration = &db.registrations[0];
+        assert_eq!(registration.origin, "https://example.com/");
+        assert_eq!(registration.registration_id, 5);
+        assert_eq!(registration.scope.as_deref(), Some("https://example.com/"));
+        assert_eq!(
+            registration.script_url.as_deref(),
+            Some("https://example.com/sw.js")
+        );
+        assert_eq!(registration.version_id, Some(8));
+        assert_eq!(registration.is_active, Some(true));
+        assert_eq!(registration.has_fetch_handler, Some(false));
+        assert_eq!(
+            registration.last_update_check_time,
+            Some(13_345_678_901_234_567)
+        );
+        assert_eq!(registration.resources_total_size, Some(2048));
+
+        let resources: Vec<(u64, Option<&str>, Option<u64>)> = registration
+            .resources
+            .iter()
+            .map(|resource| (resource.resource_id, resource.url.as_deref(), resource.size))
+            .collect();
+        assert_eq!(
+            resources,
+            vec![
+                (10, Some("https://example.com/sw.js"), Some(1500)),
+                (11, Some("https://example.com/lib.js"), Some(548)),
+            ]
+        );
+        assert_eq!(
+            registration.user_data,
+            vec![("push_token".to_string(), "abc".to_string())]
+        );
+    }
+
+    #[test]
+    fn deleted_registration_keeps_last_data() {
+        let records = [
+            // out of seq order, build sorts by seq
+            record(b"REG:https://example.com/\x005", 7, None),
+            record(
+                b"REG:https://example.com/\x005",
+                4,
+                Some(&registration_data(8)),
+            ),
+        ];
+
+        let db = build(&records);
+        let registration = &db.registrations[0];
+        assert_eq!(registration.state, 0);
+        assert_eq!(registration.seq, 7);
+        assert_eq!(registration.version_id, Some(8));
+        assert_eq!(
+            registration.script_url.as_deref(),
+            Some("https://example.com/sw.js")
+        );
+    }
+
+    #[test]
+    fn malformed_keys_skipped() {
+        let records = [
+            record(b"REG:https://example.com/", 1, Some(&registration_data(8))),
+            record(
+                b"REG:https://example.com/\x00x",
+                2,
+                Some(&registration_data(8)),
+            ),
+            record(b"RES:8", 3, Some(&resource_record(1, "u", 1))),
+            record(b"REG_USER_DATA:x\x00key", 4, Some(b"v")),
+        ];
+        assert!(build(&records).registrations.is_empty());
+    }
+}
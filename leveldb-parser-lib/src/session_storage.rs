@@ -0,0 +1,323 @@
+use std::collections::HashMap;
+
+use crate::database::DbRecord;
+use crate::decoder;
+
+// Session Storage keys:
+//   next-map-id                    => next free map id (ASCII)
+//   namespace-<guid>-<origin>      => map id of the area (ASCII)
+//   map-<map id>-<key>             => value (UTF-16LE)
+// -----------------------------------------------------------------------------
+pub struct SessionStorage {
+    pub next_map_id: Option<u64>,
+    pub namespaces: Vec<Namespace>,
+    pub entries: Vec<SessionStorageEntry>,
+}
+
+pub struct Namespace {
+    pub guid: String,
+    pub origin: String,
+    pub map_id: Option<u64>,
+    pub seq: u64,
+    pub state: u8,
+}
+
+pub struct SessionStorageEntry {
+    pub map_id: u64,
+    pub origin: Option<String>,
+    pub namespace_guids: Vec<String>,
+    pub key: String,
+    pub value: String,
+    pub seq: u64,
+    pub state: u8,
+    pub file_path: String,
+}
+
+// -----------------------------------------------------------------------------
+// decoded key and value of one row, namespace rows show guid, origin and map
+// id, map rows keep their key with the UTF-16LE value decoded
+pub fn decode_session_storage_kv(key: &[u8], value: Option<&[u8]>) -> (String, String) {
+    let key_str = decoder::bytes_to_utf8_lossy(key);
+    let value_str = |v_bytes: &[u8]| {
+        if key_str.starts_with("map-")
+            && let Some(s) = decoder::try_utf16le(v_bytes)
+        {
+            s
+        } else {
+            decoder::bytes_to_utf8_lossy(v_bytes)
+        }
+    };
+
+    if key_str == "next-map-id" {
+        let v = match value {
+            Some(v_bytes) => match parse_ascii_u64(v_bytes) {
+                Some(map_id) => format!("next map id: {}", map_id),
+                None => value_str(v_bytes),
+            },
+            None => String::new(),
+        };
+        return (key_str.clone(), v);
+    }
+
+    if let Some(rest) = key_str.strip_prefix("namespace-")
+        && let Some((guid, origin)) = split_namespace_key(rest)
+    {
+        let k = format!("namespace: {}, origin: {}", guid, origin);
+        let v = match value {
+            Some(v_bytes) => match parse_ascii_u64(v_bytes) {
+                Some(map_id) => format!("map id: {}", map_id),
+                None => value_str(v_bytes),
+            },
+            None => String::new(),
+        };
+        return (k, v);
+    }
+
+    (key_str.clone(), value.map(value_str).unwrap_or_default())
+}
+
+pub fn build(records: &[DbRecord]) -> SessionStorage {
+    let mut next_map_id = None;
+    let mut namespaces = Vec::new();
+    let mut map_entries = Vec::new();
+
+    for record in records {
+        let key = decoder::bytes_to_utf8_lossy(&record.key);
+
+        if key == "next-map-id" {
+            if let Some(id) = record.value.as_deref().and_then(parse_ascii_u64) {
+                next_map_id = Some(id);
+            }
+        } else if let Some(rest) = key.strip_prefix("namespace-") {
+            let Some((guid, origin)) = split_namespace_key(rest) else {
+                continue;
+            };
+            namespaces.push(Namespace {
+                guid: guid.to_string(),
+                origin: origin.to_string(),
+                map_id: record.value.as_deref().and_then(parse_ascii_u64),
+                seq: record.seq,
+                state: record.state,
+            });
+        } else if let Some(rest) = key.strip_prefix("map-") {
+            let Some((map_id, entry_key)) = rest.split_once('-') else {
+                continue;
+            };
+            let Ok(map_id) = map_id.parse::<u64>() else {
+                continue;
+            };
+            let (_, value) = decode_session_storage_kv(&record.key, record.value.as_deref());
+            map_entries.push(SessionStorageEntry {
+                map_id,
+                origin: None,
+                namespace_guids: Vec::new(),
+                key: entry_key.to_string(),
+                value,
+                seq: record.seq,
+                state: record.state,
+                file_path: record.file_path.clone(),
+            });
+        }
+    }
+
+    // every namespace version that ever pointed to a map (cloned tabs share maps)
+    let mut map_owners: HashMap<u64, (String, Vec<String>)> = HashMap::new();
+    for namespace in &namespaces {
+        if let Some(map_id) = namespace.map_id {
+            let (_, guids) = map_owners
+                .entry(map_id)
+                .or_insert_with(|| (namespace.origin.clone(), Vec::new()));
+            if !guids.contains(&namespace.guid) {
+                guids.push(namespace.guid.clone());
+            }
+        }
+    }
+
+    for entry in &mut map_entries {
+        if let Some((origin, guids)) = map_owners.get(&entry.map_id) {
+            entry.origin = Some(origin.clone());
+            entry.namespace_guids = guids.clone();
+        }
+    }
+
+    SessionStorage {
+        next_map_id,
+        namespaces,
+        entries: map_entries,
+    }
+}
+
+// "<guid>-<origin>", guids are 36 chars (Chromium replaces '-' with '_')
+fn split_namespace_key(rest: &str) -> Option<(&str, &str)> {
+    if rest.len() > 37 && rest.as_bytes()[36] == b'-' {
+        Some((&rest[..36], &rest[37..]))
+    } else {
+        rest.split_once('-')
+    }
+}
+
+fn parse_ascii_u64(bytes: &[u8]) -> Option<u64> {
+    std::str::from_utf8(bytes).ok()?.trim().parse().ok()
+}
+
+// -----------------------------------------------------------------------------
+pub mod export {
+    use super::*;
+
+    pub fn csv_string(session: &SessionStorage) -> String {
+        let mut csv = String::new();
+        // Header
+        csv.push_str("\"Seq\",\"Namespace\",\"Origin\",\"MapId\",\"K\",\"V\",\"St\",\"FP\"\n");
+
+        for entry in &session.entries {
+            let state_str = match entry.state {
+                0 => "deleted",
+                1 => "live",
+                _ => "unknown",
+            };
+
+            csv.push_str(&format!(
+                "\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\"\n",
+                entry.seq,
+                entry.namespace_guids.join(" ").replace("\"", "\"\""),
+                entry.origin.as_deref().unwrap_or("").replace("\"", "\"\""),
+                entry.map_id,
+                entry.key.replace("\"", "\"\""),
+                entry.value.replace("\"", "\"\""),
+                state_str,
+                entry.file_path.replace("\"", "\"\""),
+            ));
+        }
+
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GUID_A: &str = "8f3a1c2e_4b5d_4e6f_9a7b_1c2d3e4f5a6b";
+    const GUID_B: &str = "0a1b2c3d_4e5f_4a6b_8c7d_9e0f1a2b3c4d";
+
+    fn record(key: &str, seq: u64, value: Option<&[u8]>) -> DbRecord {
+        DbRecord {
+            seq,
+            state: value.is_some() as u8,
+            key: key.as_bytes().to_vec(),
+            value: value.map(|value| value.to_vec()),
+            file_path: "000003.log".to_string(),
+            block_offset: 0,
+            key_offset: 0,
+        }
+    }
+
+    fn utf16le(s: &str) -> Vec<u8> {
+        s.encode_utf16()
+            .flat_map(|unit| unit.to_le_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn namespace_key_split_at_guid() {
+        // origins contain '-' as well, the 36 char guid decides the split
+        let rest = format!("{}-https://my-site.example/", GUID_A);
+        assert_eq!(
+            split_namespace_key(&rest),
+            Some((GUID_A, "https://my-site.example/"))
+        );
+        assert_eq!(
+            split_namespace_key("short-https://example.com/"),
+            Some(("short", "https://example.com/"))
+        );
+        assert_eq!(split_namespace_key("noseparator"), None);
+    }
+
+    #[test]
+    fn entries_attributed_to_namespace_and_origin() {
+        let value = utf16le("dark");
+        let other_value = utf16le("1");
+        let records = [
+            record("next-map-id", 1, Some(b"12")),
+            record(
+                &format!("namespace-{}-https://example.com/", GUID_A),
+                2,
+                Some(b"4"),
+            ),
+            // cloned tab shares the map of the original namespace
+            record(
+                &format!("namespace-{}-https://example.com/", GUID_B),
+                3,
+                Some(b"4"),
+            ),
+            record(
+                &format!("namespace-{}-https://other.example/", GUID_A),
+                4,
+                Some(b"7"),
+            ),
+            record("map-4-theme", 5, Some(&value)),
+            record("map-7-step", 6, Some(&other_value)),
+            record("map-9-orphan", 7, None),
+        ];
+
+        let session = build(&records);
+
+        assert_eq!(session.next_map_id, Some(12));
+        assert_eq!(session.namespaces.len(), 3);
+        assert_eq!(session.namespaces[0].guid, GUID_A);
+        assert_eq!(session.namespaces[0].origin, "https://example.com/");
+        assert_eq!(session.namespaces[0].map_id, Some(4));
+
+        let theme = &session.entries[0];
+        assert_eq!(theme.map_id, 4);
+        assert_eq!(theme.key, "theme");
+        assert_eq!(theme.value, "dark");
+        assert_eq!(theme.origin.as_deref(), Some("https://example.com/"));
+        assert_eq!(theme.namespace_guids, vec![GUID_A, GUID_B]);
+
+        let step = &session.entries[1];
+        assert_eq!(step.origin.as_deref(), Some("https://other.example/"));
+        assert_eq!(step.namespace_guids, vec![GUID_A]);
+        assert_eq!(step.value, "1");
+
+        // deleted entry of a map no namespace points to
+        let orphan = &session.entries[2];
+        assert_eq!(orphan.origin, None);
+        assert!(orphan.namespace_guids.is_empty());
+        assert_eq!(orphan.state, 0);
+    }
+
+    #[test]
+    fn next_map_id_keeps_last_valid_value() {
+        let records = [
+            record("next-map-id", 1, Some(b"3")),
+            record("next-map-id", 2, Some(b"x")),
+        ];
+        assert_eq!(build(&records).next_map_id, Some(3));
+        assert_eq!(build(&[]).next_map_id, None);
+    }
+
+    #[test]
+    fn decode_namespace_and_map_rows() {
+        let namespace_key = format!("namespace-{}-https://example.com/", GUID_A);
+        assert_eq!(
+            decode_session_storage_kv(namespace_key.as_bytes(), Some(b"4")),
+            (
+                format!("namespace: {}, origin: https://example.com/", GUID_A),
+                "map id: 4".to_string()
+            )
+        );
+        assert_eq!(
+            decode_session_storage_kv(b"next-map-id", Some(b"12")),
+            ("next-map-id".to_string(), "next map id: 12".to_string())
+        );
+        assert_eq!(
+            decode_session_storage_kv(b"map-4-theme", Some(&utf16le("dark"))),
+            ("map-4-theme".to_string(), "dark".to_string())
+        );
+        assert_eq!(
+            decode_session_storage_kv(b"map-4-theme", None),
+            ("map-4-theme".to_string(), String::new())
+        );
+    }
+}
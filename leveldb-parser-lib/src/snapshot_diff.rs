@@ -0,0 +1,320 @@
+use std::collections::BTreeMap;
+
+use crate::database::{self, Database, DbRecord};
+use crate::decoder::StorageKind;
+use crate::decoder_registry::{DecodeContext, DecoderRegistry};
+
+// differences between two acquisitions of the same database, per user key
+// the record with the highest sequence number of each snapshot is compared
+// -----------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChangeKind {
+    Added,      // live in new only (or deleted before)
+    Removed,    // live in old, no record in new (e.g. compacted away)
+    Changed,    // live in both, other value
+    Rewritten,  // live in both, same value under a newer sequence number
+    Tombstoned, // live in old, deleted in new
+}
+
+pub struct Version {
+    pub seq: u64,
+    pub state: u8,
+    pub value: String, // decoded
+    pub kind: String,
+    pub file_path: String,
+}
+
+pub struct DiffEntry {
+    pub change: ChangeKind,
+    pub key: Vec<u8>,
+    pub key_str: String, // decoded
+    pub old: Option<Version>,
+    pub new: Option<Version>,
+}
+
+#[derive(Default)]
+pub struct DiffSummary {
+    pub added: usize,
+    pub removed: usize,
+    pub changed: usize,
+    pub rewritten: usize,
+    pub tombstoned: usize,
+    pub unchanged: usize,
+}
+
+impl ChangeKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChangeKind::Added => "added",
+            ChangeKind::Removed => "removed",
+            ChangeKind::Changed => "changed",
+            ChangeKind::Rewritten => "rewritten",
+            ChangeKind::Tombstoned => "tombstoned",
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+pub fn diff_databases(old: &Database, new: &Database) -> (Vec<DiffEntry>, DiffSummary) {
+    diff_records(
+        &database::collect_records(old),
+        old.storage_kind,
+        &database::collect_records(new),
+        new.storage_kind,
+        &DecoderRegistry::default(),
+    )
+}
+
+// entries ordered by user key
+pub fn diff_records(
+    old_records: &[DbRecord],
+    old_kind: StorageKind,
+    new_records: &[DbRecord],
+    new_kind: StorageKind,
+    registry: &DecoderRegistry,
+) -> (Vec<DiffEntry>, DiffSummary) {
+    let old_latest = latest_by_key(old_records);
+    let new_latest = latest_by_key(new_records);
+
+    let mut keys: Vec<&[u8]> = old_latest
+        .keys()
+        .chain(new_latest.keys())
+        .copied()
+        .collect();
+    keys.sort();
+    keys.dedup();
+
+    let mut entries = Vec::new();
+    let mut summary = DiffSummary::default();
+
+    for key in keys {
+        let old = old_latest.get(key).copied();
+        let new = new_latest.get(key).copied();
+        let old_live = old.is_some_and(|record| record.state != 0);
+        let new_live = new.is_some_and(|record| record.state != 0);
+
+        let change = match (old, new) {
+            (_, Some(_)) if !old_live && new_live => ChangeKind::Added,
+            (Some(_), None) if old_live => ChangeKind::Removed,
+            (Some(_), Some(_)) if old_live && !new_live => ChangeKind::Tombstoned,
+            (Some(old), Some(new)) if old_live && new_live && old.value != new.value => {
+                ChangeKind::Changed
+            }
+            (Some(old), Some(new)) if old_live && new_live && old.seq != new.seq => {
+                ChangeKind::Rewritten
+            }
+            _ => {
+                summary.unchanged += 1;
+                continue;
+            }
+        };
+
+        match change {
+            ChangeKind::Added => summary.added += 1,
+            ChangeKind::Removed => summary.removed += 1,
+            ChangeKind::Changed => summary.changed += 1,
+            ChangeKind::Rewritten => summary.rewritten += 1,
+            ChangeKind::Tombstoned => summary.tombstoned += 1,
+        }
+
+        let old = old.map(|record| version(record, old_kind, registry));
+        let new = new.map(|record| version(record, new_kind, registry));
+        // decoded key of the newest version
+        let key_str = match (&old, &new) {
+            (_, Some((key_str, _))) | (Some((key_str, _)), None) => key_str.clone(),
+            (None, None) => String::new(),
+        };
+
+        entries.push(DiffEntry {
+            change,
+            key: key.to_vec(),
+            key_str,
+            old: old.map(|(_, version)| version),
+            new: new.map(|(_, version)| version),
+        });
+    }
+
+    (entries, summary)
+}
+
+// newest record (highest seq) of every user key
+fn latest_by_key(records: &[DbRecord]) -> BTreeMap<&[u8], &DbRecord> {
+    let mut latest: BTreeMap<&[u8], &DbRecord> = BTreeMap::new();
+    for record in records {
+        latest
+            .entry(record.key.as_slice())
+            .and_modify(|current| {
+                if record.seq >= current.seq {
+                    *current = record;
+                }
+            })
+            .or_insert(record);
+    }
+    latest
+}
+
+// decoded key and version of a record
+fn version(
+    record: &DbRecord,
+    storage_kind: StorageKind,
+    registry: &DecoderRegistry,
+) -> (String, Version) {
+    let (key_str, value, kind) = registry.decode_kv(&DecodeContext {
+        path: &record.file_path,
+        storage_kind,
+        key: &record.key,
+        value: record.value.as_deref(),
+    });
+    let version = Version {
+        seq: record.seq,
+        state: record.state,
+        value,
+        kind,
+        file_path: record.file_path.clone(),
+    };
+    (key_str, version)
+}
+
+// -----------------------------------------------------------------------------
+pub mod export {
+    use super::*;
+
+    pub fn csv_string(entries: &[DiffEntry]) -> String {
+        let mut csv = String::new();
+        // Header
+        csv.push_str(
+            "\"Change\",\"K\",\"OldSeq\",\"OldSt\",\"OldV\",\"NewSeq\",\"NewSt\",\"NewV\",\"Kind\",\"OldFP\",\"NewFP\"\n",
+        );
+
+        for entry in entries {
+            let (old_seq, old_state, old_value, old_path) = version_columns(&entry.old);
+            let (new_seq, new_state, new_value, new_path) = version_columns(&entry.new);
+            let kind = entry
+                .new
+                .as_ref()
+                .or(entry.old.as_ref())
+                .map_or("", |version| version.kind.as_str());
+
+            csv.push_str(&format!(
+                "\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\",\"{}\"\n",
+                entry.change.name(),
+                entry.key_str.replace("\"", "\"\""),
+                old_seq,
+                old_state,
+                old_value,
+                new_seq,
+                new_state,
+                new_value,
+                kind,
+                old_path,
+                new_path,
+            ));
+        }
+
+        csv
+    }
+
+    fn version_columns(version: &Option<Version>) -> (String, &'static str, String, String) {
+        match version {
+            Some(version) => (
+                version.seq.to_string(),
+                match version.state {
+                    0 => "deleted",
+                    1 => "live",
+                    _ => "unknown",
+                },
+                version.value.replace("\"", "\"\""),
+                version.file_path.replace("\"", "\"\""),
+            ),
+            None => (String::new(), "", String::new(), String::new()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(key: &str, seq: u64, value: Option<&str>) -> DbRecord {
+        DbRecord {
+            seq,
+            state: value.is_some() as u8,
+            key: key.as_bytes().to_vec(),
+            value: value.map(|value| value.as_bytes().to_vec()),
+            file_path: "000005.log".to_string(),
+            block_offset: 0,
+            key_offset: 0,
+        }
+    }
+
+    #[test]
+    fn diff_records_change_kinds() {
+        let old = [
+            record("changed", 1, Some("a")),
+            record("removed", 2, Some("b")),
+            record("rewritten", 3, Some("c")),
+            record("tombstoned", 4, Some("d")),
+            record("unchanged", 5, Some("e")),
+            record("readded", 6, None),
+        ];
+        let new = [
+            record("added", 10, Some("f")),
+            record("changed", 1, Some("a")),
+            record("changed", 11, Some("a2")),
+            record("readded", 12, Some("g")),
+            record("rewritten", 13, Some("c")),
+            record("tombstoned", 14, None),
+            record("unchanged", 5, Some("e")),
+        ];
+
+        let (entries, summary) = diff_records(
+            &old,
+            StorageKind::Generic,
+            &new,
+            StorageKind::Generic,
+            &DecoderRegistry::default(),
+        );
+
+        let changes: Vec<(&[u8], ChangeKind)> = entries
+            .iter()
+            .map(|entry| (entry.key.as_slice(), entry.change))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                (b"added".as_slice(), ChangeKind::Added),
+                (b"changed".as_slice(), ChangeKind::Changed),
+                (b"readded".as_slice(), ChangeKind::Added),
+                (b"removed".as_slice(), ChangeKind::Removed),
+                (b"rewritten".as_slice(), ChangeKind::Rewritten),
+                (b"tombstoned".as_slice(), ChangeKind::Tombstoned),
+            ]
+        );
+        assert_eq!(summary.added, 2);
+        assert_eq!(summary.removed, 1);
+        assert_eq!(summary.changed, 1);
+        assert_eq!(summary.rewritten, 1);
+        assert_eq!(summary.tombstoned, 1);
+        assert_eq!(summary.unchanged, 1);
+
+        let changed = &entries[1];
+        assert_eq!(changed.old.as_ref().unwrap().seq, 1);
+        assert_eq!(changed.new.as_ref().unwrap().seq, 11);
+        assert!(entries[3].new.is_none());
+    }
+
+    #[test]
+    fn diff_records_deleted_in_both_is_unchanged() {
+        let old = [record("gone", 1, None)];
+        let new = [record("gone", 2, None)];
+        let (entries, summary) = diff_records(
+            &old,
+            StorageKind::Generic,
+            &new,
+            StorageKind::Generic,
+            &DecoderRegistry::default(),
+        );
+        assert!(entries.is_empty());
+        assert_eq!(summary.unchanged, 1);
+    }
+}
@@ -0,0 +1,404 @@
+use std::cmp::Ordering;
+use std::fs::File;
+use std::io::{self, BufReader, Cursor};
+
+use crate::codec::CodecRegistry;
+use crate::database::DbRecord;
+use crate::ldb_parser::{self, BloomFilter, Footer, IndexBlock, KeyValPair};
+
+// lookups on a single table without parsing all data blocks: the index block
+// separators select the data block, the restart array of the block the entry
+// to start the linear scan at
+//   keys are compared bytewise, IndexedDB tables (idb_cmp1) are not supported
+// -----------------------------------------------------------------------------
+// largest internal key trailer of a user key (max sequence, type value)
+const SEEK_TRAILER: u64 = (0x00FF_FFFF_FFFF_FFFF << 8) | 0x1;
+
+pub struct TableReader {
+    reader: BufReader<File>,
+    codecs: CodecRegistry,
+    pub file_path: String,
+    pub footer: Footer,
+    pub index_block: IndexBlock,
+    pub bloom_filter: Option<BloomFilter>,
+    pub blocks_read: usize, // data blocks decoded by lookups so far
+}
+
+impl TableReader {
+    pub fn open(file_path: &str) -> io::Result<Self> {
+        Self::open_with_codecs(file_path, CodecRegistry::for_path(file_path))
+    }
+
+    // reads footer, index block and filter, data blocks are read on demand
+    pub fn open_with_codecs(file_path: &str, codecs: CodecRegistry) -> io::Result<Self> {
+        let file = File::open(file_path)?;
+        let mut reader = BufReader::new(file);
+
+        let footer = ldb_parser::read_footer(&mut reader)?;
+        let meta_index_block =
+            ldb_parser::read_index_block(&mut reader, &codecs, footer.meta_index_handle)?;
+        let bloom_filter = ldb_parser::read_meta_blocks(&mut reader, &codecs, &meta_index_block)?
+            .into_iter()
+            .find_map(|meta_block| meta_block.bloom_filter);
+        let index_block = ldb_parser::read_index_block(&mut reader, &codecs, footer.index_handle)?;
+
+        Ok(TableReader {
+            reader,
+            codecs,
+            file_path: file_path.to_string(),
+            footer,
+            index_block,
+            bloom_filter,
+            blocks_read: 0,
+        })
+    }
+
+    // newest record of the user key, deletion records included
+    pub fn get(&mut self, user_key: &[u8]) -> io::Result<Option<DbRecord>> {
+        let records = self.seek(user_key, Some(1), true, |key| key == user_key)?;
+        Ok(records.into_iter().next())
+    }
+
+    // all records of the user key, newest first
+    pub fn get_versions(&mut self, user_key: &[u8]) -> io::Result<Vec<DbRecord>> {
+        self.seek(user_key, None, true, |key| key == user_key)
+    }
+
+    // all records with start <= user key < end, None is unbounded
+    pub fn scan(&mut self, start: Option<&[u8]>, end: Option<&[u8]>) -> io::Result<Vec<DbRecord>> {
+        self.seek(start.unwrap_or_default(), None, false, |key| {
+            end.is_none_or(|end| key < end)
+        })
+    }
+
+    // -------------------------------------------------------------------------
+    // records from the first entry >= user_key on, as long as in_range holds
+    fn seek<F>(
+        &mut self,
+        user_key: &[u8],
+        limit: Option<usize>,
+        point_lookup: bool,
+        in_range: F,
+    ) -> io::Result<Vec<DbRecord>>
+    where
+        F: Fn(&[u8]) -> bool,
+    {
+        let mut target = user_key.to_vec();
+        target.extend_from_slice(&SEEK_TRAILER.to_le_bytes());
+
+        // first data block whose separator is >= target
+        let first_block = self.index_block.records.partition_point(|record| {
+            ldb_parser::compare_internal_keys(&record.key, &target) == Ordering::Less
+        });
+
+        let mut records = Vec::new();
+        for block_idx in first_block..self.index_block.records.len() {
+            let block_handle = self.index_block.records[block_idx].block_handle;
+
+            // the first block ends with a key >= target, without the user key
+            // in it no later block holds the user key either
+            if block_idx == first_block
+                && point_lookup
+                && let Some(bloom_filter) = &self.bloom_filter
+                && !bloom_filter.may_contain(block_handle.offset, user_key)
+            {
+                break;
+            }
+
+            let raw_block = ldb_parser::read_raw_block(
+                &mut self.reader,
+                &self.codecs,
+                block_handle.offset,
+                block_handle.size,
+            )
+            .map_err(|e| {
+                io::Error::new(
+                    e.kind(),
+                    format!("Block at offset {}: {}", block_handle.offset, e),
+                )
+            })?;
+            if let Some(e) = raw_block.decompress_error {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Block at offset {}: {}", block_handle.offset, e),
+                ));
+            }
+            self.blocks_read += 1;
+
+            let entries = if block_idx == first_block {
+                seek_in_block(&raw_block.data, &target)?
+            } else {
+                read_entries_from(&raw_block.data, 0)?
+            };
+
+            for entry in entries {
+                let record = ldb_parser::data_record(entry);
+                if !in_range(&record.key) {
+                    return Ok(records);
+                }
+                records.push(DbRecord {
+                    seq: record.seq,
+                    state: record.state,
+                    key: record.key,
+                    value: (record.state != 0).then_some(record.value),
+                    file_path: self.file_path.clone(),
+                    block_offset: block_handle.offset,
+                    key_offset: block_handle.offset + record.entry.key_offset,
+                });
+                if limit.is_some_and(|limit| records.len() >= limit) {
+                    return Ok(records);
+                }
+            }
+        }
+
+        Ok(records)
+    }
+}
+
+// -----------------------------------------------------------------------------
+// entries >= target, starting at the last restart point with a smaller key
+fn seek_in_block(data: &[u8], target: &[u8]) -> io::Result<Vec<KeyValPair>> {
+    let (restart_points, restart_array_offset) = ldb_parser::read_restart_array(data)?;
+    let restart_points: Vec<u32> = restart_points
+        .into_iter()
+        .filter(|&point| (point as u64) < restart_array_offset)
+        .collect();
+
+    // binary search over the full keys at the restart points
+    let mut start = 0;
+    let (mut low, mut high) = (0, restart_points.len());
+    while low < high {
+        let mid = (low + high) / 2;
+        match restart_key(data, restart_points[mid]) {
+            Some(key) if ldb_parser::compare_internal_keys(&key, target) == Ordering::Less => {
+                start = restart_points[mid];
+                low = mid + 1;
+            }
+            Some(_) => high = mid,
+            // broken restart point, scan the whole block
+            None => {
+                start = 0;
+                break;
+            }
+        }
+    }
+
+    Ok(read_entries_from(data, start)?
+        .into_iter()
+        .skip_while(|entry| ldb_parser::compare_internal_keys(&entry.key, target) == Ordering::Less)
+        .collect())
+}
+
+// key of the entry at a restart point (stored without shared bytes)
+fn restart_key(data: &[u8], point: u32) -> Option<Vec<u8>> {
+    let mut cursor = Cursor::new(data);
+    cursor.set_position(point as u64);
+    let entry = ldb_parser::read_block_entry(&mut cursor, &[]).ok()?;
+    (entry.shared_len == 0).then_some(entry.key)
+}
+
+// entries from offset to the restart array, stops at the first broken entry
+fn read_entries_from(data: &[u8], offset: u32) -> io::Result<Vec<KeyValPair>> {
+    let (_, restart_array_offset) = ldb_parser::read_restart_array(data)?;
+    let mut cursor = Cursor::new(data);
+    cursor.set_position(offset as u64);
+
+    let mut entries = Vec::new();
+    let mut prev_key = Vec::new();
+    while cursor.position() < restart_array_offset {
+        match ldb_parser::read_block_entry(&mut cursor, &prev_key) {
+            Ok(entry) if cursor.position() <= restart_array_offset => {
+                prev_key = entry.key.clone();
+                entries.push(entry);
+            }
+            _ => break,
+        }
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+    use std::path::PathBuf;
+
+    type Row = (Vec<u8>, u64, u8, Option<Vec<u8>>);
+
+    // key0000 .. key0118 (even numbers only), two versions of key0014 across
+    // the first block boundary, key0060 deleted, 8 entries per block
+    fn write_fixture(name: &str) -> PathBuf {
+        let mut records: Vec<(Vec<u8>, u64, u8, Vec<u8>)> = Vec::new();
+        for i in (0..120).step_by(2) {
+            let key = format!("key{:04}", i).into_bytes();
+            let value = format!("value{:04}", i).repeat(10).into_bytes();
+            if i == 14 {
+                records.push((key.clone(), 500, 1, b"newer".to_vec()));
+            }
+            if i == 60 {
+                records.push((key, 400 + i, 0, Vec::new()));
+            } else {
+                records.push((key, 100 + i, 1, value));
+            }
+        }
+        let records: Vec<(&[u8], u64, u8, &[u8])> = records
+            .iter()
+            .map(|(key, seq, state, value)| (key.as_slice(), *seq, *state, value.as_slice()))
+            .collect();
+
+        let path = std::env::temp_dir().join(format!(
+            "leveldb-parser-{}-{}.ldb",
+            std::process::id(),
+            name
+        ));
+        ldb_parser::write_table(&path, &records, 8, 4).unwrap();
+        path
+    }
+
+    fn parsed_rows(path: &str) -> (Vec<Row>, usize) {
+        let ldb = ldb_parser::parse_file(path).unwrap();
+        assert!(
+            ldb.data_blocks
+                .iter()
+                .all(|block| { block.raw_block.crc_valid && block.issues.is_empty() })
+        );
+        let rows = ldb
+            .data_blocks
+            .iter()
+            .flat_map(|block| block.records.iter())
+            .map(|record| {
+                (
+                    record.key.clone(),
+                    record.seq,
+                    record.state,
+                    (record.state != 0).then(|| record.value.clone()),
+                )
+            })
+            .collect();
+        (rows, ldb.data_blocks.len())
+    }
+
+    fn rows(records: &[DbRecord]) -> Vec<Row> {
+        records
+            .iter()
+            .map(|record| {
+                (
+                    record.key.clone(),
+                    record.seq,
+                    record.state,
+                    record.value.clone(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn get_matches_parse_file() {
+        let path = write_fixture("get");
+        let path_str = path.to_str().unwrap();
+        let (expected, block_count) = parsed_rows(path_str);
+        assert!(block_count > 4);
+
+        let mut reader = TableReader::open(path_str).unwrap();
+        assert!(reader.bloom_filter.is_some());
+        for (idx, row) in expected.iter().enumerate() {
+            // newest version only
+            if idx > 0 && expected[idx - 1].0 == row.0 {
+                continue;
+            }
+            let record = reader.get(&row.0).unwrap().unwrap();
+            assert_eq!(rows(&[record]), vec![row.clone()]);
+        }
+
+        let deleted = reader.get(b"key0060").unwrap().unwrap();
+        assert_eq!((deleted.state, deleted.value), (0, None));
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn get_keys_on_block_boundaries() {
+        let path = write_fixture("boundary");
+        let path_str = path.to_str().unwrap();
+        let ldb = ldb_parser::parse_file(path_str).unwrap();
+        let mut reader = TableReader::open(path_str).unwrap();
+
+        for block in &ldb.data_blocks {
+            for record in [block.records.first(), block.records.last()]
+                .into_iter()
+                .flatten()
+            {
+                let versions = reader.get_versions(&record.key).unwrap();
+                assert!(
+                    versions
+                        .iter()
+                        .any(|version| version.seq == record.seq && version.key == record.key)
+                );
+            }
+        }
+
+        // the versions of key0014 end and start the first two blocks
+        let versions = reader.get_versions(b"key0014").unwrap();
+        let seqs: Vec<u64> = versions.iter().map(|version| version.seq).collect();
+        assert_eq!(seqs, vec![500, 114]);
+        assert_ne!(versions[0].block_offset, versions[1].block_offset);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn get_missing_keys() {
+        let path = write_fixture("missing");
+        let mut reader = TableReader::open(path.to_str().unwrap()).unwrap();
+
+        // before the first and after the last key
+        assert!(reader.get(b"a").unwrap().is_none());
+        assert!(reader.get(b"z").unwrap().is_none());
+        assert_eq!(reader.blocks_read, 0);
+
+        // between stored keys, the bloom filter skips most blocks
+        let missing: Vec<Vec<u8>> = (1..119)
+            .step_by(2)
+            .map(|i| format!("key{:04}", i).into_bytes())
+            .collect();
+        for key in &missing {
+            assert!(reader.get(key).unwrap().is_none());
+        }
+        assert!(reader.blocks_read < 1 + missing.len() / 4);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn scan_matches_parse_file() {
+        let path = write_fixture("scan");
+        let path_str = path.to_str().unwrap();
+        let (expected, _) = parsed_rows(path_str);
+        let mut reader = TableReader::open(path_str).unwrap();
+
+        assert_eq!(rows(&reader.scan(None, None).unwrap()), expected);
+
+        let in_range = |start: &[u8], end: &[u8]| -> Vec<Row> {
+            expected
+                .iter()
+                .filter(|row| row.0.as_slice() >= start && row.0.as_slice() < end)
+                .cloned()
+                .collect()
+        };
+
+        // start between two separators (inside a block), end not stored
+        let records = reader.scan(Some(b"key0031"), Some(b"key0071")).unwrap();
+        assert_eq!(rows(&records), in_range(b"key0031", b"key0071"));
+        assert_eq!(records.first().unwrap().key, b"key0032");
+
+        // start on a separator, end exclusive
+        let records = reader.scan(Some(b"key0014"), Some(b"key0020")).unwrap();
+        assert_eq!(rows(&records), in_range(b"key0014", b"key0020"));
+        assert_eq!(records.len(), 4);
+
+        assert!(reader.scan(Some(b"z"), None).unwrap().is_empty());
+
+        fs::remove_file(path).unwrap();
+    }
+}
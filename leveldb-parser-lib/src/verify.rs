@@ -0,0 +1,684 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::database;
+use crate::decoder;
+use crate::ldb_parser::{self, LdbFile};
+use crate::log_parser::{self, LogFile};
+use crate::manifest_parser::{self, VersionState};
+
+// consistency of a database directory: CURRENT -> manifest -> live tables,
+// table key ranges and sequence numbers against the manifest, block CRCs
+// -----------------------------------------------------------------------------
+pub struct Check {
+    pub name: &'static str,
+    pub passed: bool,
+    pub details: Vec<String>, // failures, or what was checked
+}
+
+pub struct VerifyReport {
+    pub dir_path: String,
+    pub manifest_name: Option<String>,
+    pub checks: Vec<Check>,
+}
+
+impl VerifyReport {
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|check| check.passed)
+    }
+}
+
+impl Check {
+    fn new(name: &'static str) -> Self {
+        Check {
+            name,
+            passed: true,
+            details: Vec::new(),
+        }
+    }
+
+    fn fail(&mut self, detail: String) {
+        self.passed = false;
+        self.details.push(detail);
+    }
+}
+
+// -----------------------------------------------------------------------------
+pub fn verify_dir(dir_path: &str) -> io::Result<VerifyReport> {
+    let dir = Path::new(dir_path);
+    let mut checks = Vec::new();
+
+    // numbered files of the directory
+    let mut tables: BTreeMap<u64, String> = BTreeMap::new();
+    let mut logs: BTreeMap<u64, String> = BTreeMap::new();
+    for entry in fs::read_dir(dir)?.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some(file_no) = database::parse_file_no(&name) else {
+            continue;
+        };
+        let path = entry.path().to_string_lossy().to_string();
+        if name.ends_with(".ldb") || name.ends_with(".sst") {
+            tables.insert(file_no, path);
+        } else if name.ends_with(".log") {
+            logs.insert(file_no, path);
+        }
+    }
+
+    // CURRENT
+    let mut current_check = Check::new("CURRENT");
+    let manifest_name = match read_current(dir) {
+        Ok(name) if dir.join(&name).is_file() => {
+            current_check.details.push(format!("Points to {}", name));
+            Some(name)
+        }
+        Ok(name) => {
+            current_check.fail(format!("Manifest {} does not exist", name));
+            None
+        }
+        Err(e) => {
+            current_check.fail(e.to_string());
+            None
+        }
+    };
+    checks.push(current_check);
+
+    // Manifest
+    let mut manifest_check = Check::new("Manifest");
+    let version = match &manifest_name {
+        Some(name) => match manifest_parser::parse_file(&dir.join(name).to_string_lossy()) {
+            Ok(manifest) => {
+                for block in manifest.blocks.iter().filter(|block| !block.crc_valid) {
+                    manifest_check.fail(format!("Block at offset {}: CRC failed", block.offset));
+                }
+                let version = manifest_parser::replay(&manifest);
+                if version.last_seq.is_none() {
+                    manifest_check.fail("No LastSeq entry".to_string());
+                }
+                if version.log_number.is_none() {
+                    manifest_check.fail("No LogNumber entry".to_string());
+                }
+                Some(version)
+            }
+            Err(e) => {
+                manifest_check.fail(format!("{}: {}", name, e));
+                None
+            }
+        },
+        None => {
+            manifest_check.fail("No manifest to check".to_string());
+            None
+        }
+    };
+    if let Some(version) = &version {
+        manifest_check.details.push(format!(
+            "{} live table(s), LastSeq: {}, LogNumber: {}",
+            version.live_files.len(),
+            opt_to_string(version.last_seq),
+            opt_to_string(version.log_number)
+        ));
+    }
+    checks.push(manifest_check);
+
+    // Tables of the directory, parse errors are reported by the CRC check
+    let parsed_tables: BTreeMap<u64, io::Result<LdbFile>> = tables
+        .iter()
+        .map(|(&file_no, path)| (file_no, ldb_parser::parse_file(path)))
+        .collect();
+    let parsed_logs: BTreeMap<u64, io::Result<LogFile>> = logs
+        .iter()
+        .map(|(&file_no, path)| (file_no, log_parser::parse_file(path)))
+        .collect();
+
+    if let Some(version) = &version {
+        checks.push(check_live_files(version, &tables));
+        checks.push(check_key_ranges(version, &parsed_tables));
+        checks.push(check_sequence_numbers(
+            version,
+            &parsed_tables,
+            &parsed_logs,
+        ));
+        checks.push(check_orphans(version, &tables));
+    } else {
+        // the checks against the manifest fail without one
+        for name in [
+            "Live files",
+            "Key ranges",
+            "Sequence numbers",
+            "Orphan tables",
+        ] {
+            let mut check = Check::new(name);
+            check.fail("Not run: no readable manifest".to_string());
+            checks.push(check);
+        }
+    }
+    checks.push(check_crcs(&tables, &parsed_tables, &logs, &parsed_logs));
+
+    Ok(VerifyReport {
+        dir_path: dir_path.to_string(),
+        manifest_name,
+        checks,
+    })
+}
+
+// manifest file name of CURRENT ("MANIFEST-000002\n")
+pub fn read_current(dir: &Path) -> io::Result<String> {
+    let content = fs::read(dir.join("CURRENT"))?;
+    let name = String::from_utf8_lossy(&content);
+    let Some(name) = name.strip_suffix('\n') else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "CURRENT does not end with a newline",
+        ));
+    };
+    if !name.starts_with("MANIFEST-") || name.contains(['/', '\\']) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("CURRENT holds no manifest name: '{}'", name),
+        ));
+    }
+    Ok(name.to_string())
+}
+
+// -----------------------------------------------------------------------------
+fn check_live_files(version: &VersionState, tables: &BTreeMap<u64, String>) -> Check {
+    let mut check = Check::new("Live files");
+
+    for table in &version.live_files {
+        match tables.get(&table.file_no) {
+            Some(path) => match fs::metadata(path) {
+                Ok(metadata) if metadata.len() != table.file_size => check.fail(format!(
+                    "{}: size {} Bytes, manifest records {} Bytes",
+                    path,
+                    metadata.len(),
+                    table.file_size
+                )),
+                Ok(_) => {}
+                Err(e) => check.fail(format!("{}: {}", path, e)),
+            },
+            None => check.fail(format!(
+                "Table {} (level {}) is missing",
+                table.file_no, table.level
+            )),
+        }
+    }
+
+    if check.passed {
+        check.details.push(format!(
+            "{} live table(s) present with the recorded size",
+            version.live_files.len()
+        ));
+    }
+    check
+}
+
+// first / last internal key of each table against AddFile smallest / largest
+fn check_key_ranges(
+    version: &VersionState,
+    parsed_tables: &BTreeMap<u64, io::Result<LdbFile>>,
+) -> Check {
+    let mut check = Check::new("Key ranges");
+
+    for table in &version.live_files {
+        let Some(Ok(ldb)) = parsed_tables.get(&table.file_no) else {
+            continue;
+        };
+        let first = ldb
+            .data_blocks
+            .iter()
+            .find_map(|block| block.records.first());
+        let last = ldb
+            .data_blocks
+            .iter()
+            .rev()
+            .find_map(|block| block.records.last());
+        let (Some(first), Some(last)) = (first, last) else {
+            check.fail(format!("Table {} has no records", table.file_no));
+            continue;
+        };
+
+        if (first.key.as_slice(), first.seq, first.state)
+            != (table.sm_key.as_slice(), table.sm_seq, table.sm_state)
+        {
+            check.fail(format!(
+                "Table {}: smallest key '{}' @ {} : {}, manifest records '{}' @ {} : {}",
+                table.file_no,
+                decoder::bytes_to_ascii_with_hex(&first.key),
+                first.seq,
+                first.state,
+                decoder::bytes_to_ascii_with_hex(&table.sm_key),
+                table.sm_seq,
+                table.sm_state
+            ));
+        }
+        if (last.key.as_slice(), last.seq, last.state)
+            != (table.lg_key.as_slice(), table.lg_seq, table.lg_state)
+        {
+            check.fail(format!(
+                "Table {}: largest key '{}' @ {} : {}, manifest records '{}' @ {} : {}",
+                table.file_no,
+                decoder::bytes_to_ascii_with_hex(&last.key),
+                last.seq,
+                last.state,
+                decoder::bytes_to_ascii_with_hex(&table.lg_key),
+                table.lg_seq,
+                table.lg_state
+            ));
+        }
+    }
+
+    if check.passed {
+        check
+            .details
+            .push("Table key ranges match the manifest".to_string());
+    }
+    check
+}
+
+// tables and logs older than LogNumber were written before LastSeq, records of
+// the current log (LogNumber and later) are not yet in the manifest
+fn check_sequence_numbers(
+    version: &VersionState,
+    parsed_tables: &BTreeMap<u64, io::Result<LdbFile>>,
+    parsed_logs: &BTreeMap<u64, io::Result<LogFile>>,
+) -> Check {
+    let mut check = Check::new("Sequence numbers");
+    let Some(last_seq) = version.last_seq else {
+        check.fail("No LastSeq to check against".to_string());
+        return check;
+    };
+
+    for (file_no, ldb) in parsed_tables {
+        let Ok(ldb) = ldb else {
+            continue;
+        };
+        let max_seq = ldb
+            .data_blocks
+            .iter()
+            .flat_map(|block| block.records.iter())
+            .map(|record| record.seq)
+            .max();
+        if let Some(max_seq) = max_seq
+            && max_seq > last_seq
+        {
+            check.fail(format!(
+                "Table {}: seq {} exceeds LastSeq {}",
+                file_no, max_seq, last_seq
+            ));
+        }
+    }
+
+    let log_number = version.log_number.unwrap_or(0);
+    for (file_no, log) in parsed_logs.range(..log_number) {
+        let Ok(log) = log else {
+            continue;
+        };
+        let max_seq = log
+            .batches
+            .iter()
+            .flat_map(|batch| batch.records.iter())
+            .map(|record| record.seq)
+            .max();
+        if let Some(max_seq) = max_seq
+            && max_seq > last_seq
+        {
+            check.fail(format!(
+                "Log {}: seq {} exceeds LastSeq {}",
+                file_no, max_seq, last_seq
+            ));
+        }
+    }
+
+    if check.passed {
+        check
+            .details
+            .push(format!("No table or old log exceeds LastSeq {}", last_seq));
+    }
+    check
+}
+
+// tables in the directory that the current version does not reference
+fn check_orphans(version: &VersionState, tables: &BTreeMap<u64, String>) -> Check {
+    let mut check = Check::new("Orphan tables");
+
+    for (file_no, path) in tables {
+        if !version
+            .live_files
+            .iter()
+            .any(|table| table.file_no == *file_no)
+        {
+            check.fail(format!("{} is not a live table", path));
+        }
+    }
+
+    if check.passed {
+        check.details.push("No orphan tables".to_string());
+    }
+    check
+}
+
+fn check_crcs(
+    tables: &BTreeMap<u64, String>,
+    parsed_tables: &BTreeMap<u64, io::Result<LdbFile>>,
+    logs: &BTreeMap<u64, String>,
+    parsed_logs: &BTreeMap<u64, io::Result<LogFile>>,
+) -> Check {
+    let mut check = Check::new("CRCs");
+    let mut block_count = 0;
+
+    for (file_no, ldb) in parsed_tables {
+        let path = &tables[file_no];
+        let ldb = match ldb {
+            Ok(ldb) => ldb,
+            Err(e) => {
+                check.fail(format!("{}: {}", path, e));
+                continue;
+            }
+        };
+        if !ldb.footer.is_valid {
+            check.fail(format!("{}: invalid footer magic", path));
+        }
+
+        let blocks = [
+            (
+                "Meta index block",
+                &ldb.meta_index_block.raw_block,
+                ldb.meta_index_block.block_handle.offset,
+            ),
+            (
+                "Index block",
+                &ldb.index_block.raw_block,
+                ldb.index_block.block_handle.offset,
+            ),
+        ]
+        .into_iter()
+        .chain(
+            ldb.meta_blocks
+                .iter()
+                .map(|block| ("Meta block", &block.raw_block, block.block_handle.offset)),
+        )
+        .chain(
+            ldb.data_blocks
+                .iter()
+                .map(|block| ("Data block", &block.raw_block, block.block_handle.offset)),
+        );
+        for (name, raw_block, offset) in blocks {
+            block_count += 1;
+            if !raw_block.crc_valid {
+                check.fail(format!(
+                    "{}: {} at offset {}: CRC failed",
+                    path, name, offset
+                ));
+            }
+            if let Some(e) = &raw_block.decompress_error {
+                check.fail(format!("{}: {} at offset {}: {}", path, name, offset, e));
+            }
+        }
+    }
+
+    for (file_no, log) in parsed_logs {
+        let path = &logs[file_no];
+        match log {
+            Ok(log) => {
+                for block in &log.blocks {
+                    block_count += 1;
+                    if !block.crc_valid {
+                        check.fail(format!(
+                            "{}: Block at offset {}: CRC failed",
+                            path, block.offset
+                        ));
+                    }
+                }
+            }
+            Err(e) => check.fail(format!("{}: {}", path, e)),
+        }
+    }
+
+    if check.passed {
+        check
+            .details
+            .push(format!("{} block CRCs verified", block_count));
+    }
+    check
+}
+
+fn opt_to_string(value: Option<u64>) -> String {
+    value.map_or("-".to_string(), |value| value.to_string())
+}
+
+// -----------------------------------------------------------------------------
+pub mod display {
+    use super::*;
+
+    pub fn print_report(report: &VerifyReport) -> io::Result<()> {
+        writeln!(io::stdout(), "Database: {}", report.dir_path)?;
+        for check in &report.checks {
+            let status = if check.passed { "PASS" } else { "FAIL" };
+            writeln!(io::stdout(), "[{}] {}", status, check.name)?;
+            for detail in &check.details {
+                writeln!(io::stdout(), "       {}", detail)?;
+            }
+        }
+        let result = if report.passed() { "PASS" } else { "FAIL" };
+        writeln!(io::stdout(), "Result: {}", result)?;
+        Ok(())
+    }
+}
+
+pub mod export {
+    use super::*;
+
+    pub fn csv_string(report: &VerifyReport) -> String {
+        let mut csv = String::new();
+        // Header
+        csv.push_str("\"Check\",\"Result\",\"Detail\",\"DB\"\n");
+
+        for check in &report.checks {
+            let status = if check.passed { "pass" } else { "fail" };
+            for detail in &check.details {
+                csv.push_str(&format!(
+                    "\"{}\",\"{}\",\"{}\",\"{}\"\n",
+                    check.name,
+                    status,
+                    detail.replace("\"", "\"\""),
+                    report.dir_path.replace("\"", "\"\""),
+                ));
+            }
+        }
+
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path::PathBuf;
+
+    use crate::manifest_parser::TableFile;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("leveldb-parser-{}-{}", std::process::id(), name));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn table_file(file_no: u64, smallest: (&[u8], u64), largest: (&[u8], u64)) -> TableFile {
+        TableFile {
+            level: 0,
+            file_no,
+            file_size: 0,
+            sm_key: smallest.0.to_vec(),
+            sm_seq: smallest.1,
+            sm_state: 1,
+            lg_key: largest.0.to_vec(),
+            lg_seq: largest.1,
+            lg_state: 1,
+        }
+    }
+
+    fn version(live_files: Vec<TableFile>, last_seq: u64, log_number: u64) -> VersionState {
+        VersionState {
+            last_seq: Some(last_seq),
+            log_number: Some(log_number),
+            live_files,
+            ..Default::default()
+        }
+    }
+
+    // table with the keys a @ 5, b @ 6, c @ 9
+    fn parsed_table(dir: &Path, file_no: u64) -> io::Result<LdbFile> {
+        let path = dir.join(format!("{:06}.ldb", file_no));
+        let records: [(&[u8], u64, u8, &[u8]); 3] =
+            [(b"a", 5, 1, b"1"), (b"b", 6, 1, b"2"), (b"c", 9, 1, b"3")];
+        ldb_parser::write_table(&path, &records, 2, 1).unwrap();
+        ldb_parser::parse_file(&path.to_string_lossy())
+    }
+
+    fn log_with_seqs(seqs: &[u64]) -> io::Result<LogFile> {
+        Ok(LogFile {
+            blocks: Vec::new(),
+            batches: vec![log_parser::Batch {
+                header: log_parser::BatchHeader {
+                    seq_no: seqs[0],
+                    rec_count: seqs.len() as u32,
+                },
+                records: seqs
+                    .iter()
+                    .map(|&seq| log_parser::Record {
+                        seq,
+                        state: 1,
+                        key: b"k".to_vec(),
+                        key_offset: 0,
+                        value: Some(b"v".to_vec()),
+                        value_offset: None,
+                    })
+                    .collect(),
+                offset: 0,
+            }],
+            storage_kind: decoder::StorageKind::Generic,
+        })
+    }
+
+    #[test]
+    fn current_file_validation() {
+        let dir = temp_dir("current");
+        let current = |content: &[u8]| {
+            fs::write(dir.join("CURRENT"), content).unwrap();
+            read_current(&dir).map_err(|e| e.to_string())
+        };
+
+        assert_eq!(
+            current(b"MANIFEST-000002\n"),
+            Ok("MANIFEST-000002".to_string())
+        );
+        assert_eq!(
+            current(b"MANIFEST-000002"),
+            Err("CURRENT does not end with a newline".to_string())
+        );
+        assert_eq!(
+            current(b"../MANIFEST-000002\n"),
+            Err("CURRENT holds no manifest name: '../MANIFEST-000002'".to_string())
+        );
+        assert_eq!(
+            current(b"MANIFEST-000002/x\n"),
+            Err("CURRENT holds no manifest name: 'MANIFEST-000002/x'".to_string())
+        );
+        assert_eq!(
+            current(b"MANIFEST-00\\0002\n"),
+            Err("CURRENT holds no manifest name: 'MANIFEST-00\\0002'".to_string())
+        );
+        assert_eq!(
+            current(b"000005.ldb\n"),
+            Err("CURRENT holds no manifest name: '000005.ldb'".to_string())
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn key_ranges_against_manifest() {
+        let dir = temp_dir("key-ranges");
+        let parsed_tables =
+            BTreeMap::from([(5, parsed_table(&dir, 5)), (6, parsed_table(&dir, 6))]);
+
+        let matching = version(
+            vec![
+                table_file(5, (b"a", 5), (b"c", 9)),
+                // not parsed, skipped
+                table_file(7, (b"x", 1), (b"y", 2)),
+            ],
+            9,
+            3,
+        );
+        let check = check_key_ranges(&matching, &parsed_tables);
+        assert!(check.passed, "{:?}", check.details);
+
+        let mismatching = version(
+            vec![
+                table_file(5, (b"a", 4), (b"c", 9)),
+                table_file(6, (b"a", 5), (b"d", 9)),
+            ],
+            9,
+            3,
+        );
+        let check = check_key_ranges(&mismatching, &parsed_tables);
+        assert!(!check.passed);
+        assert_eq!(
+            check.details,
+            vec![
+                "Table 5: smallest key 'a' @ 5 : 1, manifest records 'a' @ 4 : 1",
+                "Table 6: largest key 'c' @ 9 : 1, manifest records 'd' @ 9 : 1",
+            ]
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn sequence_numbers_up_to_log_number() {
+        let dir = temp_dir("sequence-numbers");
+        let parsed_tables = BTreeMap::from([(5, parsed_table(&dir, 5))]);
+        let parsed_logs = BTreeMap::from([
+            (3, log_with_seqs(&[7, 8])),
+            // current log (LogNumber 4) is not in LastSeq yet
+            (4, log_with_seqs(&[10, 11])),
+        ]);
+
+        let check =
+            check_sequence_numbers(&version(Vec::new(), 9, 4), &parsed_tables, &parsed_logs);
+        assert!(check.passed, "{:?}", check.details);
+
+        // LastSeq below the table, LogNumber after the newer log
+        let check =
+            check_sequence_numbers(&version(Vec::new(), 8, 5), &parsed_tables, &parsed_logs);
+        assert_eq!(
+            check.details,
+            vec![
+                "Table 5: seq 9 exceeds LastSeq 8",
+                "Log 4: seq 11 exceeds LastSeq 8",
+            ]
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn orphan_tables() {
+        let tables = BTreeMap::from([
+            (5, "db/000005.ldb".to_string()),
+            (8, "db/000008.ldb".to_string()),
+        ]);
+        let live = version(vec![table_file(5, (b"a", 1), (b"b", 2))], 9, 3);
+
+        let check = check_orphans(&live, &tables);
+        assert!(!check.passed);
+        assert_eq!(check.details, vec!["db/000008.ldb is not a live table"]);
+
+        let check = check_orphans(&live, &BTreeMap::from([(5, "db/000005.ldb".to_string())]));
+        assert!(check.passed);
+    }
+}